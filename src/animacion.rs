@@ -0,0 +1,199 @@
+use nalgebra::{Vector3, Point3};
+use crate::camara::Camara;
+use crate::escena::Escena;
+use crate::muestreo::Muestreador;
+use crate::pasadas::PasadasAuxiliares;
+use crate::reduccion_ruido::{self, ConfiguracionRuido};
+use crate::renderizador::{ConfiguracionAdaptativa, ModoRender, RenderProgresivo};
+use crate::tonos::{self, Revelado};
+use std::fs;
+use std::path::Path;
+
+/// Estado de la cámara en un instante de la animación
+#[derive(Clone, Copy, Debug)]
+pub struct ClaveCamara {
+    pub tiempo: f64,              // Segundos desde el inicio
+    pub posicion: Point3<f64>,
+    pub objetivo: Point3<f64>,
+    pub campo_vision: f64,        // Grados
+}
+
+/// Recorrido de cámara interpolado con splines Catmull-Rom entre fotogramas clave
+pub struct TrayectoriaCamara {
+    claves: Vec<ClaveCamara>,
+    pub ciclica: bool,   // La última clave coincide con la primera (órbitas)
+}
+
+/// Parámetros para renderizar una secuencia de cuadros numerados
+pub struct ConfiguracionSecuencia {
+    pub ancho: usize,
+    pub alto: usize,
+    pub cuadros_por_segundo: f64,
+    pub muestreo: Muestreador,
+    pub modo: ModoRender,
+    pub adaptativo: Option<ConfiguracionAdaptativa>,   // Además escribe `muestras_0001.png`...
+    pub revelado: Revelado,
+    pub exportar_exr: bool,                            // Además escribe `cuadro_0001.exr`...
+    pub pasadas: bool,                                 // Además escribe `cuadro_0001_normal.png`...
+    pub reducir_ruido: Option<ConfiguracionRuido>,     // Filtra el cuadro guiado por las pasadas
+}
+
+// Catmull-Rom uniforme entre p1 y p2
+fn catmull_rom(p0: f64, p1: f64, p2: f64, p3: f64, u: f64) -> f64 {
+    let u2 = u * u;
+    let u3 = u2 * u;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * u
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u3)
+}
+
+fn catmull_rom_punto(p: [&Point3<f64>; 4], u: f64) -> Point3<f64> {
+    Point3::new(
+        catmull_rom(p[0].x, p[1].x, p[2].x, p[3].x, u),
+        catmull_rom(p[0].y, p[1].y, p[2].y, p[3].y, u),
+        catmull_rom(p[0].z, p[1].z, p[2].z, p[3].z, u),
+    )
+}
+
+impl TrayectoriaCamara {
+    pub fn nueva() -> Self {
+        Self {
+            claves: Vec::new(),
+            ciclica: false,
+        }
+    }
+
+    // Inserta la clave manteniendo el orden temporal
+    pub fn agregar_clave(&mut self, clave: ClaveCamara) {
+        let indice = self.claves.partition_point(|c| c.tiempo <= clave.tiempo);
+        self.claves.insert(indice, clave);
+    }
+
+    /// Órbita alrededor del objetivo construida con `Camara::rotar_horizontal`
+    pub fn giratoria(camara: &Camara, vueltas: f64, duracion: f64) -> Self {
+        let claves_por_vuelta = 16;
+        let pasos = ((vueltas.abs() * claves_por_vuelta as f64).ceil() as usize).max(2);
+        let angulo_paso = vueltas * 2.0 * std::f64::consts::PI / pasos as f64;
+
+        let mut orbita = Camara::nueva(camara.posicion, camara.objetivo, camara.arriba,
+                                       camara.campo_vision, camara.aspecto);
+        let mut trayectoria = Self::nueva();
+        for paso in 0..=pasos {
+            trayectoria.agregar_clave(ClaveCamara {
+                tiempo: duracion * paso as f64 / pasos as f64,
+                posicion: orbita.posicion,
+                objetivo: orbita.objetivo,
+                campo_vision: orbita.campo_vision,
+            });
+            orbita.rotar_horizontal(angulo_paso);
+        }
+        trayectoria.ciclica = (vueltas.fract()).abs() < 1e-9;
+        trayectoria
+    }
+
+    pub fn inicio(&self) -> f64 {
+        self.claves.first().map(|c| c.tiempo).unwrap_or(0.0)
+    }
+
+    pub fn duracion(&self) -> f64 {
+        match (self.claves.first(), self.claves.last()) {
+            (Some(primera), Some(ultima)) => ultima.tiempo - primera.tiempo,
+            _ => 0.0,
+        }
+    }
+
+    // Clave vecina; en trayectorias cíclicas se salta la última (igual a la primera)
+    fn vecina(&self, indice: isize) -> &ClaveCamara {
+        let n = self.claves.len() as isize;
+        if self.ciclica && n > 2 {
+            &self.claves[indice.rem_euclid(n - 1) as usize]
+        } else {
+            &self.claves[indice.clamp(0, n - 1) as usize]
+        }
+    }
+
+    /// Estado interpolado en el tiempo `t` (se sujeta al rango de la trayectoria)
+    pub fn evaluar(&self, t: f64) -> Option<ClaveCamara> {
+        let primera = self.claves.first()?;
+        let ultima = self.claves.last()?;
+        if self.claves.len() == 1 || t <= primera.tiempo {
+            return Some(ClaveCamara { tiempo: t, ..*primera });
+        }
+        if t >= ultima.tiempo {
+            return Some(ClaveCamara { tiempo: t, ..*ultima });
+        }
+
+        let i = self.claves.partition_point(|c| c.tiempo <= t) as isize - 1;
+        let (c0, c1, c2, c3) = (self.vecina(i - 1), self.vecina(i), self.vecina(i + 1), self.vecina(i + 2));
+        let u = (t - c1.tiempo) / (c2.tiempo - c1.tiempo).max(1e-9);
+
+        Some(ClaveCamara {
+            tiempo: t,
+            posicion: catmull_rom_punto([&c0.posicion, &c1.posicion, &c2.posicion, &c3.posicion], u),
+            objetivo: catmull_rom_punto([&c0.objetivo, &c1.objetivo, &c2.objetivo, &c3.objetivo], u),
+            campo_vision: catmull_rom(c0.campo_vision, c1.campo_vision, c2.campo_vision, c3.campo_vision, u),
+        })
+    }
+
+    pub fn camara_en(&self, t: f64, aspecto: f64) -> Option<Camara> {
+        let clave = self.evaluar(t)?;
+        Some(Camara::nueva(clave.posicion, clave.objetivo, Vector3::new(0.0, 1.0, 0.0),
+                           clave.campo_vision, aspecto))
+    }
+
+    /// Número de cuadros que cubren la trayectoria a paso fijo
+    pub fn numero_cuadros(&self, cuadros_por_segundo: f64) -> usize {
+        (self.duracion() * cuadros_por_segundo).round() as usize + 1
+    }
+}
+
+/// Renderiza la trayectoria con el ray tracer en CPU como `cuadro_0001.png`, `cuadro_0002.png`...
+/// Devuelve el número de cuadros escritos.
+pub fn renderizar_secuencia(escena: &Escena, trayectoria: &TrayectoriaCamara,
+                            config: &ConfiguracionSecuencia, directorio: &str) -> Result<usize, String> {
+    fs::create_dir_all(directorio).map_err(|e| format!("No se pudo crear {}: {}", directorio, e))?;
+
+    let aspecto = config.ancho as f64 / config.alto as f64;
+    let inicio = trayectoria.inicio();
+    let total = trayectoria.numero_cuadros(config.cuadros_por_segundo);
+    let mut progresivo = RenderProgresivo::nuevo(config.ancho, config.alto).con_muestreo(config.muestreo);
+
+    for cuadro in 0..total {
+        let t = inicio + cuadro as f64 / config.cuadros_por_segundo;
+        let camara = match trayectoria.camara_en(t, aspecto) {
+            Some(camara) => camara,
+            None => break,
+        };
+
+        progresivo.reiniciar();
+        match &config.adaptativo {
+            Some(adaptativo) => {
+                progresivo.renderizar_adaptativo(escena, &camara, config.modo, adaptativo);
+                let ruta_mapa = Path::new(directorio).join(format!("muestras_{:04}.png", cuadro + 1));
+                tonos::guardar_imagen(&ruta_mapa, config.ancho, config.alto, &progresivo.mapa_muestras(),
+                                      &Revelado::lineal())?;
+            }
+            None => progresivo.agregar_pasada(escena, &camara, config.modo),
+        }
+
+        let guias = (config.pasadas || config.reducir_ruido.is_some())
+            .then(|| PasadasAuxiliares::calcular(escena, &camara, config.ancho, config.alto));
+        let mut imagen = progresivo.imagen();
+        if let (Some(ruido), Some(guias)) = (&config.reducir_ruido, &guias) {
+            imagen = reduccion_ruido::reducir_ruido(&imagen, guias, ruido);
+        }
+        if config.exportar_exr {
+            let ruta_exr = Path::new(directorio).join(format!("cuadro_{:04}.exr", cuadro + 1));
+            tonos::guardar_imagen(&ruta_exr, config.ancho, config.alto, &imagen, &config.revelado)?;
+        }
+        let ruta = Path::new(directorio).join(format!("cuadro_{:04}.png", cuadro + 1));
+        tonos::guardar_imagen(&ruta, config.ancho, config.alto, &imagen, &config.revelado)?;
+        if let (true, Some(guias)) = (config.pasadas, &guias) {
+            guias.guardar(Path::new(directorio), &format!("cuadro_{:04}", cuadro + 1), config.exportar_exr)?;
+        }
+        println!("🎞️ Cuadro {}/{} -> {}", cuadro + 1, total, ruta.display());
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::mundo::*;
+use crate::terreno::Bioma;
+
+/// Forma de la copa
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FormaCopa {
+    Esfera,                        // Bola achatada alrededor de la punta del tronco
+    Cono,                          // Capas que se estrechan hacia arriba, alternando anchas y estrechas
+    Plana,                         // Disco ancho de dos capas sobre la punta
+}
+
+/// Especie de árbol: bloques, tamaños y reglas de crecimiento. Los rangos son
+/// (mínimo, máximo) y cada árbol elige dentro de ellos según su semilla.
+#[derive(Clone, Debug)]
+pub struct Especie {
+    pub nombre: &'static str,
+    pub tronco: IdBloque,
+    pub hojas: IdBloque,
+    pub altura: (i32, i32),        // Bloques de tronco
+    pub grosor: i32,               // Lado del tronco (la jungla es de 2×2)
+    pub torcido: f64,              // Probabilidad de que el tronco se tuerza a media altura
+    pub ramas: (i32, i32),
+    pub largo_rama: (i32, i32),
+    pub forma_copa: FormaCopa,
+    pub radio_copa: (f64, f64),
+    pub alto_copa: i32,
+    pub densidad_borde: f64,       // Probabilidad de cada hoja del borde de la copa
+}
+
+impl Especie {
+    pub fn roble() -> Self {
+        Self {
+            nombre: "roble",
+            tronco: TRONCO_ROBLE,
+            hojas: HOJAS_ROBLE,
+            altura: (4, 6),
+            grosor: 1,
+            torcido: 0.0,
+            ramas: (0, 2),
+            largo_rama: (2, 3),
+            forma_copa: FormaCopa::Esfera,
+            radio_copa: (2.0, 2.8),
+            alto_copa: 4,
+            densidad_borde: 0.6,
+        }
+    }
+
+    pub fn abedul() -> Self {
+        Self {
+            nombre: "abedul",
+            tronco: TRONCO_ABEDUL,
+            hojas: HOJAS_ABEDUL,
+            altura: (5, 7),
+            grosor: 1,
+            torcido: 0.0,
+            ramas: (0, 0),
+            largo_rama: (0, 0),
+            forma_copa: FormaCopa::Esfera,
+            radio_copa: (1.6, 2.0),
+            alto_copa: 4,
+            densidad_borde: 0.5,
+        }
+    }
+
+    pub fn cerezo() -> Self {
+        Self {
+            nombre: "cerezo",
+            tronco: TRONCO_CEREZO,
+            hojas: HOJAS_CEREZO,
+            altura: (4, 6),
+            grosor: 1,
+            torcido: 0.3,
+            ramas: (2, 3),
+            largo_rama: (2, 3),
+            forma_copa: FormaCopa::Esfera,
+            radio_copa: (2.6, 3.2),
+            alto_copa: 3,
+            densidad_borde: 0.7,
+        }
+    }
+
+    pub fn abeto() -> Self {
+        Self {
+            nombre: "abeto",
+            tronco: TRONCO_ABETO,
+            hojas: HOJAS_ABETO,
+            altura: (6, 9),
+            grosor: 1,
+            torcido: 0.0,
+            ramas: (0, 0),
+            largo_rama: (0, 0),
+            forma_copa: FormaCopa::Cono,
+            radio_copa: (2.4, 3.0),
+            alto_copa: 0,
+            densidad_borde: 0.8,
+        }
+    }
+
+    pub fn jungla() -> Self {
+        Self {
+            nombre: "jungla",
+            tronco: TRONCO_JUNGLA,
+            hojas: HOJAS_JUNGLA,
+            altura: (10, 15),
+            grosor: 2,
+            torcido: 0.0,
+            ramas: (2, 4),
+            largo_rama: (3, 4),
+            forma_copa: FormaCopa::Esfera,
+            radio_copa: (3.5, 4.5),
+            alto_copa: 4,
+            densidad_borde: 0.7,
+        }
+    }
+
+    pub fn acacia() -> Self {
+        Self {
+            nombre: "acacia",
+            tronco: TRONCO_ACACIA,
+            hojas: HOJAS_ACACIA,
+            altura: (4, 6),
+            grosor: 1,
+            torcido: 0.9,
+            ramas: (1, 2),
+            largo_rama: (2, 3),
+            forma_copa: FormaCopa::Plana,
+            radio_copa: (2.5, 3.2),
+            alto_copa: 2,
+            densidad_borde: 0.5,
+        }
+    }
+
+    pub fn todas() -> Vec<Especie> {
+        vec![Self::roble(), Self::abedul(), Self::cerezo(), Self::abeto(), Self::jungla(), Self::acacia()]
+    }
+
+    /// Especie que crece en el bioma; `azar` (0 a 1) elige entre las posibles
+    pub fn para_bioma(bioma: Bioma, azar: f64) -> Self {
+        match bioma {
+            Bioma::Llanura if azar < 0.8 => Self::roble(),
+            Bioma::Llanura => Self::abedul(),
+            Bioma::Bosque if azar < 0.45 => Self::roble(),
+            Bioma::Bosque if azar < 0.8 => Self::abedul(),
+            Bioma::Bosque => Self::jungla(),
+            Bioma::Desierto => Self::acacia(),
+            Bioma::MontanaNevada => Self::abeto(),
+            Bioma::Cerezal if azar < 0.85 => Self::cerezo(),
+            Bioma::Cerezal => Self::abedul(),
+        }
+    }
+
+    /// Genera un árbol de la especie; la misma semilla da siempre el mismo árbol
+    pub fn generar(&self, semilla: u64) -> Arbol {
+        let mut rng = StdRng::seed_from_u64(semilla);
+        let mut bloques = BTreeMap::new();
+        let altura = rng.gen_range(self.altura.0..=self.altura.1);
+
+        // Tronco, que puede torcerse hacia un lado a partir de media altura
+        let torcido = rng.gen_bool(self.torcido);
+        let (dx, dz) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)];
+        let codo = rng.gen_range(altura / 2..=(altura - 2).max(altura / 2));
+        let (mut x, mut z) = (0, 0);
+        for y in 0..altura {
+            if torcido && y > codo && y - codo <= 2 {
+                x += dx;
+                z += dz;
+            }
+            for (gx, gz) in self.seccion() {
+                bloques.insert((x + gx, y, z + gz), Pieza::Tronco);
+            }
+        }
+        let punta = (x, altura, z);
+
+        // Ramas en diagonal hacia arriba desde el eje del tronco, con un penacho de hojas
+        let eje = (self.grosor.max(1) - 1) as f64 / 2.0;
+        for _ in 0..rng.gen_range(self.ramas.0..=self.ramas.1) {
+            let angulo = rng.gen_range(0.0..std::f64::consts::TAU);
+            let largo = rng.gen_range(self.largo_rama.0..=self.largo_rama.1);
+            let inicio = rng.gen_range(altura / 2..altura);
+            let mut fin = (0, inicio, 0);
+            for paso in 1..=largo {
+                let desplazamiento = paso as f64 + eje;
+                fin = (
+                    (eje + angulo.cos() * desplazamiento).round() as i32,
+                    inicio + paso / 2,
+                    (eje + angulo.sin() * desplazamiento).round() as i32,
+                );
+                bloques.insert(fin, Pieza::Tronco);
+            }
+            esfera_hojas(&mut bloques, &mut rng, fin, 1.8, 1.2, self.densidad_borde);
+        }
+
+        let radio = rng.gen_range(self.radio_copa.0..=self.radio_copa.1);
+        match self.forma_copa {
+            FormaCopa::Esfera => {
+                let centro = (punta.0, punta.1 + self.alto_copa / 2 - 2, punta.2);
+                esfera_hojas(&mut bloques, &mut rng, centro, radio, self.alto_copa as f64 / 2.0 + 0.5, self.densidad_borde);
+            }
+            FormaCopa::Cono => {
+                // Desde un tercio del tronco hasta un bloque sobre la punta
+                let base = altura / 3;
+                for y in base..=altura {
+                    let subida = (y - base) as f64 / (altura + 1 - base) as f64;
+                    let ancha = (y - altura) % 2 == 0;
+                    let r = radio * (1.0 - subida) * if ancha { 1.0 } else { 0.6 } + 0.5;
+                    disco_hojas(&mut bloques, &mut rng, (punta.0, y, punta.2), r, self.densidad_borde);
+                }
+                bloques.insert(punta, Pieza::Hoja);
+            }
+            FormaCopa::Plana => {
+                disco_hojas(&mut bloques, &mut rng, punta, radio, self.densidad_borde);
+                disco_hojas(&mut bloques, &mut rng, (punta.0, punta.1 + 1, punta.2), radio - 1.0, self.densidad_borde);
+            }
+        }
+
+        let bloques: Vec<(Posicion, IdBloque)> = bloques
+            .into_iter()
+            .map(|(posicion, pieza)| (posicion, if pieza == Pieza::Tronco { self.tronco } else { self.hojas }))
+            .collect();
+        let (alto, radio_copa) = bloques.iter().fold((0, 0.0f64), |(alto, radio), &((x, y, z), _)| {
+            (alto.max(y + 1), radio.max(((x * x + z * z) as f64).sqrt()))
+        });
+        Arbol {
+            especie: self.nombre,
+            hojas: self.hojas,
+            bloques,
+            alto,
+            radio_copa,
+        }
+    }
+
+    // Columnas del tronco respecto a su esquina
+    fn seccion(&self) -> impl Iterator<Item = (i32, i32)> {
+        let grosor = self.grosor.max(1);
+        (0..grosor).flat_map(move |gx| (0..grosor).map(move |gz| (gx, gz)))
+    }
+}
+
+// Bloque del árbol en construcción: el tronco nunca se tapa con hojas
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Pieza {
+    Tronco,
+    Hoja,
+}
+
+// Hojas dentro del elipsoide de radio horizontal `radio` y vertical `alto`; en el borde
+// solo algunas, para que la copa no quede perfecta
+fn esfera_hojas(bloques: &mut BTreeMap<Posicion, Pieza>, rng: &mut StdRng, centro: Posicion, radio: f64, alto: f64,
+                densidad_borde: f64) {
+    let (rh, rv) = (radio.ceil() as i32, alto.ceil() as i32);
+    for dy in -rv..=rv {
+        for dx in -rh..=rh {
+            for dz in -rh..=rh {
+                let d = ((dx * dx + dz * dz) as f64 / (radio * radio) + (dy * dy) as f64 / (alto * alto)).sqrt();
+                if d <= 1.0 && (d < 0.75 || rng.gen_bool(densidad_borde)) {
+                    bloques.entry((centro.0 + dx, centro.1 + dy, centro.2 + dz)).or_insert(Pieza::Hoja);
+                }
+            }
+        }
+    }
+}
+
+// Capa de hojas de radio `radio` a la altura del centro, con el borde irregular
+fn disco_hojas(bloques: &mut BTreeMap<Posicion, Pieza>, rng: &mut StdRng, centro: Posicion, radio: f64,
+               densidad_borde: f64) {
+    let r = radio.ceil() as i32;
+    for dx in -r..=r {
+        for dz in -r..=r {
+            let d = ((dx * dx + dz * dz) as f64).sqrt() / radio.max(0.5);
+            if d <= 1.0 && (d < 0.75 || rng.gen_bool(densidad_borde)) {
+                bloques.entry((centro.0 + dx, centro.1, centro.2 + dz)).or_insert(Pieza::Hoja);
+            }
+        }
+    }
+}
+
+/// Árbol generado, con los bloques relativos a la base del tronco
+#[derive(Clone, Debug)]
+pub struct Arbol {
+    pub especie: &'static str,
+    pub hojas: IdBloque,
+    pub bloques: Vec<(Posicion, IdBloque)>,
+    pub alto: i32,                 // Bloques desde la base hasta la hoja más alta
+    pub radio_copa: f64,           // Distancia horizontal máxima al tronco
+}
+
+impl Arbol {
+    /// Planta el árbol con la base del tronco en `base`: el tronco reemplaza lo que haya
+    /// y las hojas solo ocupan el aire
+    pub fn plantar(&self, mundo: &mut Mundo, base: Posicion) {
+        for &((x, y, z), id) in &self.bloques {
+            let (x, y, z) = (base.0 + x, base.1 + y, base.2 + z);
+            if id == self.hojas {
+                mundo.colocar_si_aire(x, y, z, id);
+            } else {
+                mundo.colocar(x, y, z, id);
+            }
+        }
+    }
+}
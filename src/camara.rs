@@ -1,7 +1,20 @@
 use nalgebra::{Vector3, Point3};
 use crate::geometria::Rayo;
+use rand::{thread_rng, Rng};
 use std::f64::consts::PI;
 
+/// Forma en que la cámara convierte coordenadas de imagen (s, t) en rayos
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Proyeccion {
+    Perspectiva,
+    /// Rayos paralelos; `ancho` es el ancho visible en unidades del mundo
+    Ortografica { ancho: f64 },
+    /// Panorama 360° x 180° alrededor de la posición
+    Equirectangular,
+    /// Ojo de pez equidistante; `campo_vision` es el diámetro del círculo en grados
+    OjoDePez { campo_vision: f64 },
+}
+
 #[allow(dead_code)]
 pub struct Camara {
     pub posicion: Point3<f64>,
@@ -9,6 +22,7 @@ pub struct Camara {
     pub arriba: Vector3<f64>,
     pub campo_vision: f64,
     pub aspecto: f64,
+    pub proyeccion: Proyeccion,
     
     // Vectores de la base de la cámara
     pub u: Vector3<f64>,
@@ -21,6 +35,12 @@ pub struct Camara {
     pub esquina_inferior_izquierda: Point3<f64>,
     pub horizontal: Vector3<f64>,
     pub vertical: Vector3<f64>,
+    
+    // Lente delgada: profundidad de campo e inclinación del plano focal
+    pub apertura: f64,             // Radio del lente (0.0 = cámara estenopeica)
+    pub distancia_focal: f64,      // Distancia al plano enfocado a lo largo de la vista
+    pub inclinacion_focal: f64,    // Giro del plano focal en radianes (efecto tilt-shift)
+    pub autoenfoque: bool,         // Mantener el objetivo enfocado al mover la cámara
 }
 
 unsafe impl Send for Camara {}
@@ -41,6 +61,7 @@ impl Camara {
         let horizontal = u * (2.0 * mitad_ancho);
         let vertical = v * (2.0 * mitad_alto);
         let esquina_inferior_izquierda = posicion - horizontal/2.0 - vertical/2.0 - w;
+        let distancia_focal = (posicion - objetivo).magnitude();
         
         Self {
             posicion,
@@ -48,6 +69,7 @@ impl Camara {
             arriba,
             campo_vision,
             aspecto,
+            proyeccion: Proyeccion::Perspectiva,
             u,
             v,
             w,
@@ -56,12 +78,111 @@ impl Camara {
             esquina_inferior_izquierda,
             horizontal,
             vertical,
+            apertura: 0.0,
+            distancia_focal,
+            inclinacion_focal: 0.0,
+            autoenfoque: true,
         }
     }
     
+    /// Vista isométrica clásica (45° de azimut, 35.26° de elevación) de `ancho` unidades
+    pub fn isometrica(objetivo: Point3<f64>, distancia: f64, ancho: f64, aspecto: f64) -> Self {
+        let elevacion = (1.0 / 2.0_f64.sqrt()).atan();
+        let direccion = Vector3::new(
+            elevacion.cos() * (PI / 4.0).cos(),
+            elevacion.sin(),
+            elevacion.cos() * (PI / 4.0).sin(),
+        );
+        
+        Self::nueva(objetivo + direccion * distancia, objetivo, Vector3::new(0.0, 1.0, 0.0), 45.0, aspecto)
+            .con_proyeccion(Proyeccion::Ortografica { ancho })
+    }
+    
+    pub fn con_proyeccion(mut self, proyeccion: Proyeccion) -> Self {
+        self.proyeccion = proyeccion;
+        self
+    }
+    
+    // Radio del lente; valores mayores desenfocan más lo que está fuera del plano focal
+    pub fn con_apertura(mut self, apertura: f64) -> Self {
+        self.apertura = apertura.max(0.0);
+        self
+    }
+    
+    // Enfoque manual a una distancia fija (desactiva el autoenfoque)
+    pub fn con_distancia_focal(mut self, distancia_focal: f64) -> Self {
+        self.distancia_focal = distancia_focal.max(1e-3);
+        self.autoenfoque = false;
+        self
+    }
+    
+    // Inclina el plano focal alrededor del eje horizontal de la cámara (en grados).
+    // Con una apertura grande deja una franja nítida estrecha: el look de "miniatura".
+    pub fn con_inclinacion(mut self, grados: f64) -> Self {
+        self.inclinacion_focal = grados * PI / 180.0;
+        self
+    }
+    
     pub fn obtener_rayo(&self, s: f64, t: f64) -> Rayo {
+        match self.proyeccion {
+            Proyeccion::Perspectiva => self.rayo_perspectiva(s, t),
+            Proyeccion::Ortografica { ancho } => {
+                let alto = ancho / self.aspecto;
+                let origen = self.posicion + self.u * ((s - 0.5) * ancho) + self.v * ((t - 0.5) * alto);
+                Rayo::new(origen, -self.w)
+            }
+            Proyeccion::Equirectangular => {
+                let longitud = (s - 0.5) * 2.0 * PI;
+                let latitud = (t - 0.5) * PI;
+                let direccion = -self.w * (latitud.cos() * longitud.cos())
+                    + self.u * (latitud.cos() * longitud.sin())
+                    + self.v * latitud.sin();
+                Rayo::new(self.posicion, direccion)
+            }
+            Proyeccion::OjoDePez { campo_vision } => {
+                // El círculo ocupa todo el alto de la imagen
+                let x = (2.0 * s - 1.0) * self.aspecto;
+                let y = 2.0 * t - 1.0;
+                let radio = (x * x + y * y).sqrt();
+                if radio < 1e-9 {
+                    return Rayo::new(self.posicion, -self.w);
+                }
+                
+                let theta = (radio * campo_vision * PI / 360.0).min(PI);
+                let direccion = -self.w * theta.cos() + (self.u * (x / radio) + self.v * (y / radio)) * theta.sin();
+                Rayo::new(self.posicion, direccion)
+            }
+        }
+    }
+    
+    fn rayo_perspectiva(&self, s: f64, t: f64) -> Rayo {
         let direccion = self.esquina_inferior_izquierda + s * self.horizontal + t * self.vertical - self.posicion;
-        Rayo::new(self.posicion, direccion)
+        if self.apertura <= 0.0 {
+            return Rayo::new(self.posicion, direccion);
+        }
+        
+        // Punto donde el rayo estenopeico corta el plano focal (posiblemente inclinado)
+        let normal_focal = self.w * self.inclinacion_focal.cos() + self.v * self.inclinacion_focal.sin();
+        let denominador = direccion.dot(&normal_focal);
+        let t_focal = if denominador.abs() > 1e-6 {
+            -self.distancia_focal * self.inclinacion_focal.cos() / denominador
+        } else {
+            self.distancia_focal
+        };
+        let punto_focal = self.posicion + direccion * t_focal.max(1e-3);
+        
+        // Muestra uniforme en el disco del lente
+        let mut rng = thread_rng();
+        let (lente_x, lente_y) = loop {
+            let x = rng.gen_range(-1.0..1.0);
+            let y = rng.gen_range(-1.0..1.0);
+            if x * x + y * y <= 1.0 {
+                break (x * self.apertura, y * self.apertura);
+            }
+        };
+        let origen = self.posicion + self.u * lente_x + self.v * lente_y;
+        
+        Rayo::new(origen, punto_focal - origen)
     }
     
     // Funciones para rotar la cámara alrededor del objetivo
@@ -116,5 +237,9 @@ impl Camara {
         self.horizontal = self.u * (2.0 * self.mitad_ancho);
         self.vertical = self.v * (2.0 * self.mitad_alto);
         self.esquina_inferior_izquierda = self.posicion - self.horizontal/2.0 - self.vertical/2.0 - self.w;
+        
+        if self.autoenfoque {
+            self.distancia_focal = (self.posicion - self.objetivo).magnitude();
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+use nalgebra::{Matrix3, Vector3};
+
+// Convierte la luminancia de Preetham (kcd/m²) a la escala de radiancia de la escena
+const ESCALA_LUMINANCIA: f64 = 1.0 / 15.0;
+
+/// Cielo diurno analítico de Preetham, Shirley y Smits (1999)
+#[derive(Clone, Debug)]
+pub struct CieloPreetham {
+    pub hacia_sol: Vector3<f64>,   // Dirección normalizada hacia el sol
+    pub turbidez: f64,             // 2 = muy claro, 10 = brumoso
+    theta_sol: f64,
+    perez_y: [f64; 5],
+    perez_x: [f64; 5],
+    perez_yc: [f64; 5],
+    cenit: Vector3<f64>,           // (Y, x, y) en el cenit
+}
+
+// Distribución de Perez
+fn perez(coeficientes: &[f64; 5], coseno_theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coeficientes;
+    (1.0 + a * (b / coseno_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+impl CieloPreetham {
+    /// `direccion_luz` es hacia dónde viaja la luz del sol (como en `Luz::direccional`)
+    pub fn nuevo(direccion_luz: Vector3<f64>, turbidez: f64) -> Self {
+        let hacia_sol = -direccion_luz.normalize();
+        let t = turbidez.clamp(1.7, 10.0);
+        // El modelo no está definido con el sol bajo el horizonte
+        let theta_sol = hacia_sol.y.clamp(0.0, 1.0).acos().min(std::f64::consts::FRAC_PI_2 - 1e-3);
+
+        let perez_y = [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251,
+                       0.1206 * t - 2.5771, -0.0670 * t + 0.3703];
+        let perez_x = [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125,
+                       -0.0641 * t - 0.8989, -0.0033 * t + 0.0452];
+        let perez_yc = [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102,
+                        -0.0441 * t - 1.6537, -0.0109 * t + 0.0529];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f64::consts::PI - 2.0 * theta_sol);
+        let y_cenit = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let (t2, s) = (t * t, theta_sol);
+        let potencias = Vector3::new(t2, t, 1.0);
+        let angulos = nalgebra::Vector4::new(s * s * s, s * s, s, 1.0);
+        let matriz_x = nalgebra::Matrix3x4::new(
+            0.00166, -0.00375, 0.00209, 0.0,
+            -0.02903, 0.06377, -0.03202, 0.00394,
+            0.11693, -0.21196, 0.06052, 0.25886,
+        );
+        let matriz_y = nalgebra::Matrix3x4::new(
+            0.00275, -0.00610, 0.00317, 0.0,
+            -0.04214, 0.08970, -0.04153, 0.00516,
+            0.15346, -0.26756, 0.06670, 0.26688,
+        );
+        let x_cenit = potencias.dot(&(matriz_x * angulos));
+        let yc_cenit = potencias.dot(&(matriz_y * angulos));
+
+        Self {
+            hacia_sol,
+            turbidez: t,
+            theta_sol,
+            perez_y,
+            perez_x,
+            perez_yc,
+            cenit: Vector3::new(y_cenit.max(0.0), x_cenit, yc_cenit),
+        }
+    }
+
+    /// Radiancia (RGB lineal) del cielo en la dirección dada; bajo el horizonte
+    /// se usa el color del horizonte
+    pub fn radiancia(&self, direccion: &Vector3<f64>) -> Vector3<f64> {
+        let direccion = direccion.normalize();
+        let coseno_theta = direccion.y.max(0.01);
+        let gamma = direccion.dot(&self.hacia_sol).clamp(-1.0, 1.0).acos();
+
+        let relativo = |coeficientes: &[f64; 5], cenit: f64| {
+            cenit * perez(coeficientes, coseno_theta, gamma) / perez(coeficientes, 1.0, self.theta_sol)
+        };
+        let luminancia = relativo(&self.perez_y, self.cenit.x) * ESCALA_LUMINANCIA;
+        let x = relativo(&self.perez_x, self.cenit.y);
+        let y = relativo(&self.perez_yc, self.cenit.z);
+
+        xyy_a_rgb(x, y, luminancia)
+    }
+
+    /// Color del sol tras atravesar la atmósfera (dispersión de Rayleigh y
+    /// aerosoles); enrojece cuando el sol está bajo
+    pub fn transmitancia_sol(&self) -> Vector3<f64> {
+        let grados = self.theta_sol.to_degrees();
+        let masa_aire = 1.0 / (self.theta_sol.cos() + 0.15 * (93.885 - grados).powf(-1.253));
+        let beta = 0.04608 * self.turbidez - 0.04586;
+
+        // Longitudes de onda representativas de R, G y B en micrómetros
+        let longitudes = Vector3::new(0.680, 0.550, 0.440);
+        longitudes.map(|l: f64| {
+            let rayleigh = 0.008735 * l.powf(-4.08);
+            let aerosoles = beta * l.powf(-1.3);
+            (-(rayleigh + aerosoles) * masa_aire).exp()
+        })
+    }
+}
+
+// xyY (CIE 1931) -> sRGB lineal
+fn xyy_a_rgb(x: f64, y: f64, luminancia: f64) -> Vector3<f64> {
+    if y <= 1e-6 {
+        return Vector3::zeros();
+    }
+    let xyz = Vector3::new(x * luminancia / y, luminancia, (1.0 - x - y) * luminancia / y);
+    let a_srgb = Matrix3::new(
+        3.2406, -1.5372, -0.4986,
+        -0.9689, 1.8758, 0.0415,
+        0.0557, -0.2040, 1.0570,
+    );
+    (a_srgb * xyz).map(|c| c.max(0.0))
+}
@@ -0,0 +1,196 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use crate::mundo::*;
+use crate::terreno::{Bioma, GeneradorTerreno};
+
+// Intentos alrededor de cada punto activo antes de descartarlo (Bridson 2007)
+const INTENTOS_POISSON: usize = 30;
+
+/// Reglas para colocar un tipo de objeto sobre el terreno
+#[derive(Clone, Debug)]
+pub struct ReglasColocacion {
+    pub distancia_minima: f64,     // Entre dos objetos del mismo tipo
+    pub holgura: f64,              // Radio que deja libre a los objetos de otros tipos
+    pub maximo: usize,             // Objetos como mucho
+    pub fuera_del_agua: bool,      // Ni en agua ni en lava
+    pub pendiente_maxima: i32,     // Desnivel máximo con las cuatro columnas vecinas
+    pub biomas: Vec<Bioma>,        // Biomas donde puede ir (vacío = todos)
+}
+
+impl Default for ReglasColocacion {
+    fn default() -> Self {
+        Self {
+            distancia_minima: 3.0,
+            holgura: 1.0,
+            maximo: usize::MAX,
+            fuera_del_agua: true,
+            pendiente_maxima: 2,
+            biomas: Vec::new(),
+        }
+    }
+}
+
+/// Objeto colocado en una columna
+#[derive(Clone, Copy, Debug)]
+pub struct Colocacion {
+    pub x: i32,
+    pub z: i32,
+    pub y: i32,                    // Bloque de la superficie sobre el que se apoya
+    pub bioma: Bioma,
+}
+
+/// Puntos del rectángulo de `minimo` a `maximo` separados al menos `distancia` entre sí
+/// y sin huecos donde quepa otro (muestreo de disco de Poisson de Bridson). Con la misma
+/// semilla salen siempre los mismos puntos.
+pub fn disco_poisson(minimo: (f64, f64), maximo: (f64, f64), distancia: f64, semilla: u64) -> Vec<(f64, f64)> {
+    if distancia <= 0.0 || maximo.0 <= minimo.0 || maximo.1 <= minimo.1 {
+        return Vec::new();
+    }
+    let mut rng = StdRng::seed_from_u64(semilla);
+
+    // En cada celda de la rejilla cabe como mucho un punto
+    let celda = distancia / std::f64::consts::SQRT_2;
+    let ancho = ((maximo.0 - minimo.0) / celda).ceil() as i32;
+    let largo = ((maximo.1 - minimo.1) / celda).ceil() as i32;
+    let mut rejilla: Vec<Option<usize>> = vec![None; (ancho * largo) as usize];
+    let celda_de = |p: (f64, f64)| (((p.0 - minimo.0) / celda) as i32, ((p.1 - minimo.1) / celda) as i32);
+
+    let mut puntos = vec![(rng.gen_range(minimo.0..maximo.0), rng.gen_range(minimo.1..maximo.1))];
+    let (cx, cz) = celda_de(puntos[0]);
+    rejilla[(cz * ancho + cx) as usize] = Some(0);
+    let mut activos = vec![0];
+
+    while !activos.is_empty() {
+        let k = rng.gen_range(0..activos.len());
+        let centro = puntos[activos[k]];
+        let libre = |p: (f64, f64), rejilla: &[Option<usize>]| {
+            let (cx, cz) = celda_de(p);
+            (cz - 2..=cz + 2)
+                .flat_map(|vz| (cx - 2..=cx + 2).map(move |vx| (vx, vz)))
+                .filter(|&(vx, vz)| vx >= 0 && vz >= 0 && vx < ancho && vz < largo)
+                .filter_map(|(vx, vz)| rejilla[(vz * ancho + vx) as usize])
+                .all(|i| {
+                    let (dx, dz) = (puntos[i].0 - p.0, puntos[i].1 - p.1);
+                    dx * dx + dz * dz >= distancia * distancia
+                })
+        };
+
+        // Candidatos en el anillo entre `distancia` y el doble alrededor del punto activo
+        let nuevo = (0..INTENTOS_POISSON)
+            .map(|_| {
+                let angulo = rng.gen_range(0.0..std::f64::consts::TAU);
+                let radio = rng.gen_range(distancia..2.0 * distancia);
+                (centro.0 + angulo.cos() * radio, centro.1 + angulo.sin() * radio)
+            })
+            .find(|&p| {
+                p.0 >= minimo.0 && p.1 >= minimo.1 && p.0 < maximo.0 && p.1 < maximo.1 && libre(p, &rejilla)
+            });
+        match nuevo {
+            Some(p) => {
+                let (cx, cz) = celda_de(p);
+                rejilla[(cz * ancho + cx) as usize] = Some(puntos.len());
+                activos.push(puntos.len());
+                puntos.push(p);
+            }
+            None => {
+                activos.swap_remove(k);
+            }
+        }
+    }
+    puntos
+}
+
+/// Reparte objetos de varios tipos por las columnas de `minimo` a `maximo` (x, z; ambos
+/// incluidos). Cada tipo se coloca con sus reglas y deja libre su holgura a los que se
+/// colocan después. La altura de cada objeto sale de la superficie del mundo.
+pub struct Distribucion<'a> {
+    mundo: &'a Mundo,
+    generador: &'a GeneradorTerreno,
+    pub minimo: (i32, i32),
+    pub maximo: (i32, i32),
+    ocupados: Vec<(f64, f64, f64)>,    // x, z y holgura de lo ya colocado
+}
+
+impl<'a> Distribucion<'a> {
+    pub fn nueva(mundo: &'a Mundo, generador: &'a GeneradorTerreno, minimo: (i32, i32), maximo: (i32, i32)) -> Self {
+        Self {
+            mundo,
+            generador,
+            minimo,
+            maximo,
+            ocupados: Vec::new(),
+        }
+    }
+
+    /// Reserva el espacio de un objeto colocado por otra vía
+    pub fn ocupar(&mut self, x: f64, z: f64, holgura: f64) {
+        self.ocupados.push((x, z, holgura));
+    }
+
+    /// Mayor desnivel entre la columna y sus cuatro vecinas
+    pub fn pendiente(&self, x: i32, z: i32) -> i32 {
+        let altura = |x, z| self.mundo.superficie(x, z).unwrap_or(0);
+        let centro = altura(x, z);
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .map(|&(dx, dz)| (altura(x + dx, z + dz) - centro).abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// La columna cumple las reglas (sin mirar la distancia a otros objetos)
+    pub fn admite(&self, reglas: &ReglasColocacion, x: i32, z: i32) -> Option<Colocacion> {
+        let dentro = (self.minimo.0..=self.maximo.0).contains(&x) && (self.minimo.1..=self.maximo.1).contains(&z);
+        let y = self.mundo.superficie(x, z).filter(|_| dentro)?;
+        if reglas.fuera_del_agua && matches!(self.mundo.obtener(x, y, z), AGUA | LAVA) {
+            return None;
+        }
+        if self.pendiente(x, z) > reglas.pendiente_maxima {
+            return None;
+        }
+        // El bioma de la superficie que hay, ya erosionada y redondeada a bloques
+        let bioma = self.generador.muestra_con_altura(x, z, y as f64).bioma;
+        if !reglas.biomas.is_empty() && !reglas.biomas.contains(&bioma) {
+            return None;
+        }
+        Some(Colocacion { x, z, y, bioma })
+    }
+
+    /// Coloca hasta `reglas.maximo` objetos de un tipo en puntos de un disco de Poisson
+    /// que cumplen las reglas y respetan la holgura de lo ya colocado
+    pub fn colocar(&mut self, reglas: &ReglasColocacion, semilla: u64) -> Vec<Colocacion> {
+        let mut candidatos = disco_poisson(
+            (self.minimo.0 as f64 - 0.5, self.minimo.1 as f64 - 0.5),
+            (self.maximo.0 as f64 + 0.5, self.maximo.1 as f64 + 0.5),
+            reglas.distancia_minima,
+            semilla,
+        );
+        // El disco crece desde un punto: al azar, el máximo no se llena en una sola zona
+        candidatos.shuffle(&mut StdRng::seed_from_u64(semilla ^ 0xD15C_0000));
+
+        let anteriores = self.ocupados.len();
+        let mut colocados = Vec::new();
+        for (px, pz) in candidatos {
+            if colocados.len() >= reglas.maximo {
+                break;
+            }
+            let (x, z) = (px.round() as i32, pz.round() as i32);
+            let (fx, fz) = (x as f64, z as f64);
+            // Con los de otros tipos se suman las holguras; con los del mismo tipo basta
+            // con no repetir columna al redondear
+            let choca = self.ocupados.iter().enumerate().any(|(i, &(ox, oz, holgura))| {
+                let distancia = ((ox - fx).powi(2) + (oz - fz).powi(2)).sqrt();
+                distancia < if i < anteriores { holgura + reglas.holgura } else { 0.5 }
+            });
+            if choca {
+                continue;
+            }
+            if let Some(colocacion) = self.admite(reglas, x, z) {
+                self.ocupados.push((fx, fz, reglas.holgura));
+                colocados.push(colocacion);
+            }
+        }
+        colocados
+    }
+}
@@ -0,0 +1,175 @@
+use nalgebra::Vector3;
+use image::codecs::hdr::HdrDecoder;
+use rand::Rng;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+
+/// Mapa de entorno equirectangular (`.hdr`, `.exr`) con muestreo por importancia
+/// según la luminancia de cada téxel
+pub struct MapaEntorno {
+    pub ancho: usize,
+    pub alto: usize,
+    pixeles: Vec<Vector3<f64>>,
+    rotacion: f64,                 // Giro alrededor del eje Y en radianes
+    cdf_filas: Vec<f64>,           // Distribución marginal de las filas (alto + 1 valores)
+    cdf_columnas: Vec<f64>,        // Distribución de columnas de cada fila ((ancho + 1) por fila)
+}
+
+// Búsqueda del intervalo de la CDF que contiene `valor`
+fn buscar_intervalo(cdf: &[f64], valor: f64) -> usize {
+    let indice = cdf.partition_point(|&c| c <= valor);
+    indice.clamp(1, cdf.len() - 1) - 1
+}
+
+// CDF normalizada de `pesos`; devuelve también la suma
+fn construir_cdf(pesos: &[f64]) -> (Vec<f64>, f64) {
+    let mut cdf = Vec::with_capacity(pesos.len() + 1);
+    cdf.push(0.0);
+    let mut suma = 0.0;
+    for peso in pesos {
+        suma += peso;
+        cdf.push(suma);
+    }
+    if suma > 0.0 {
+        cdf.iter_mut().for_each(|c| *c /= suma);
+    } else {
+        // Mapa negro: reparto uniforme
+        let n = pesos.len() as f64;
+        cdf.iter_mut().enumerate().for_each(|(i, c)| *c = i as f64 / n);
+    }
+    (cdf, suma)
+}
+
+impl MapaEntorno {
+    pub fn cargar(ruta: &str) -> Result<Self, String> {
+        let error = |e: image::ImageError| format!("No se pudo leer el mapa de entorno {}: {}", ruta, e);
+
+        // `image::open` convierte los .hdr a 8 bits; se leen en coma flotante con su decodificador
+        let (ancho, alto, pixeles) = if ruta.to_ascii_lowercase().ends_with(".hdr") {
+            let archivo = File::open(ruta).map_err(|e| format!("No se pudo abrir {}: {}", ruta, e))?;
+            let decodificador = HdrDecoder::new(BufReader::new(archivo)).map_err(error)?;
+            let datos = decodificador.metadata();
+            let pixeles = decodificador.read_image_hdr().map_err(error)?;
+            (datos.width as usize, datos.height as usize, pixeles.iter().map(|p| p.0).collect::<Vec<_>>())
+        } else {
+            let imagen = image::open(ruta).map_err(error)?.to_rgb32f();
+            (imagen.width() as usize, imagen.height() as usize, imagen.pixels().map(|p| p.0).collect())
+        };
+
+        let pixeles = pixeles
+            .iter()
+            .map(|p| Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            .collect();
+        Self::desde_pixeles(ancho, alto, pixeles).map_err(|e| format!("{}: {}", ruta, e))
+    }
+
+    /// Mapa de `ancho` x `alto` píxeles por filas; falla si está vacío o faltan píxeles
+    pub fn desde_pixeles(ancho: usize, alto: usize, pixeles: Vec<Vector3<f64>>) -> Result<Self, String> {
+        if ancho == 0 || alto == 0 {
+            return Err(format!("mapa de entorno vacío ({}x{})", ancho, alto));
+        }
+        if pixeles.len() != ancho * alto {
+            return Err(format!("{} píxeles para un mapa de {}x{}", pixeles.len(), ancho, alto));
+        }
+
+        let mut cdf_columnas = Vec::with_capacity((ancho + 1) * alto);
+        let mut pesos_filas = Vec::with_capacity(alto);
+
+        for fila in 0..alto {
+            // sin(θ) compensa que las filas cerca de los polos cubren menos ángulo sólido
+            let seno = (PI * (fila as f64 + 0.5) / alto as f64).sin();
+            let pesos: Vec<f64> = pixeles[fila * ancho..(fila + 1) * ancho]
+                .iter()
+                .map(|p| (0.2126 * p.x + 0.7152 * p.y + 0.0722 * p.z).max(0.0) * seno)
+                .collect();
+            let (cdf, suma) = construir_cdf(&pesos);
+            cdf_columnas.extend(cdf);
+            pesos_filas.push(suma);
+        }
+        let (cdf_filas, _) = construir_cdf(&pesos_filas);
+
+        Ok(Self {
+            ancho,
+            alto,
+            pixeles,
+            rotacion: 0.0,
+            cdf_filas,
+            cdf_columnas,
+        })
+    }
+
+    /// Gira el mapa alrededor del eje vertical
+    pub fn con_rotacion(mut self, grados: f64) -> Self {
+        self.rotacion = grados.to_radians();
+        self
+    }
+
+    // Dirección del mundo -> coordenadas (u, v) en [0, 1)
+    fn coordenadas(&self, direccion: &Vector3<f64>) -> (f64, f64) {
+        let direccion = direccion.normalize();
+        let phi = direccion.z.atan2(direccion.x) - self.rotacion;
+        let u = (phi / (2.0 * PI)).rem_euclid(1.0);
+        let v = direccion.y.clamp(-1.0, 1.0).acos() / PI;
+        (u, v)
+    }
+
+    fn direccion(&self, u: f64, v: f64) -> Vector3<f64> {
+        let phi = u * 2.0 * PI + self.rotacion;
+        let theta = v * PI;
+        Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+    }
+
+    fn texel(&self, u: f64, v: f64) -> (usize, usize) {
+        let x = ((u * self.ancho as f64) as usize).min(self.ancho - 1);
+        let y = ((v * self.alto as f64) as usize).min(self.alto - 1);
+        (x, y)
+    }
+
+    pub fn radiancia(&self, direccion: &Vector3<f64>) -> Vector3<f64> {
+        let (u, v) = self.coordenadas(direccion);
+        let (x, y) = self.texel(u, v);
+        self.pixeles[y * self.ancho + x]
+    }
+
+    /// Densidad en ángulo sólido con la que `muestrear` elige la dirección
+    pub fn pdf(&self, direccion: &Vector3<f64>) -> f64 {
+        let (u, v) = self.coordenadas(direccion);
+        let (x, y) = self.texel(u, v);
+        let seno = (v * PI).sin();
+        if seno <= 1e-8 {
+            return 0.0;
+        }
+
+        let pdf_fila = (self.cdf_filas[y + 1] - self.cdf_filas[y]) * self.alto as f64;
+        let columnas = &self.cdf_columnas[y * (self.ancho + 1)..(y + 1) * (self.ancho + 1)];
+        let pdf_columna = (columnas[x + 1] - columnas[x]) * self.ancho as f64;
+        pdf_fila * pdf_columna / (2.0 * PI * PI * seno)
+    }
+
+    /// Dirección elegida en proporción a la luminancia y su densidad en ángulo sólido
+    pub fn muestrear<R: Rng>(&self, rng: &mut R) -> (Vector3<f64>, f64) {
+        let y = buscar_intervalo(&self.cdf_filas, rng.gen());
+        let columnas = &self.cdf_columnas[y * (self.ancho + 1)..(y + 1) * (self.ancho + 1)];
+        let x = buscar_intervalo(columnas, rng.gen());
+
+        // Punto al azar dentro del téxel elegido
+        let u = (x as f64 + rng.gen::<f64>()) / self.ancho as f64;
+        let v = (y as f64 + rng.gen::<f64>()) / self.alto as f64;
+        let direccion = self.direccion(u, v);
+        (direccion, self.pdf(&direccion))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rechaza_mapas_vacios() {
+        assert!(MapaEntorno::desde_pixeles(0, 4, Vec::new()).is_err());
+        assert!(MapaEntorno::desde_pixeles(4, 0, Vec::new()).is_err());
+        assert!(MapaEntorno::desde_pixeles(2, 2, vec![Vector3::zeros(); 3]).is_err());
+        assert!(MapaEntorno::desde_pixeles(2, 1, vec![Vector3::new(1.0, 1.0, 1.0); 2]).is_ok());
+    }
+}
@@ -0,0 +1,252 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Alturas continuas (en bloques) de una región de columnas
+#[derive(Clone, Debug)]
+pub struct MapaAlturas {
+    pub minimo: (i32, i32),
+    pub ancho: usize,              // Columnas en x
+    pub largo: usize,              // Columnas en z
+    pub alturas: Vec<f64>,
+}
+
+impl MapaAlturas {
+    /// Mapa de `minimo` a `maximo` (x, z; ambos incluidos) con la altura de cada columna
+    pub fn nuevo(minimo: (i32, i32), maximo: (i32, i32), altura: impl Fn(i32, i32) -> f64) -> Self {
+        let ancho = (maximo.0 - minimo.0 + 1).max(0) as usize;
+        let largo = (maximo.1 - minimo.1 + 1).max(0) as usize;
+        let alturas = (0..ancho * largo)
+            .map(|i| altura(minimo.0 + (i % ancho) as i32, minimo.1 + (i / ancho) as i32))
+            .collect();
+        Self { minimo, ancho, largo, alturas }
+    }
+
+    pub fn altura(&self, x: i32, z: i32) -> Option<f64> {
+        let (lx, lz) = (x - self.minimo.0, z - self.minimo.1);
+        if lx < 0 || lz < 0 || lx as usize >= self.ancho || lz as usize >= self.largo {
+            return None;
+        }
+        Some(self.alturas[lz as usize * self.ancho + lx as usize])
+    }
+
+    // Altura interpolada y gradiente en un punto en coordenadas locales de celda
+    fn altura_y_gradiente(&self, px: f64, pz: f64) -> (f64, f64, f64) {
+        let (cx, cz) = (px.floor() as usize, pz.floor() as usize);
+        let (u, v) = (px - cx as f64, pz - cz as f64);
+        let i = cz * self.ancho + cx;
+        let (h00, h10) = (self.alturas[i], self.alturas[i + 1]);
+        let (h01, h11) = (self.alturas[i + self.ancho], self.alturas[i + self.ancho + 1]);
+
+        let gx = (h10 - h00) * (1.0 - v) + (h11 - h01) * v;
+        let gz = (h01 - h00) * (1.0 - u) + (h11 - h10) * u;
+        let altura = h00 * (1.0 - u) * (1.0 - v) + h10 * u * (1.0 - v) + h01 * (1.0 - u) * v + h11 * u * v;
+        (altura, gx, gz)
+    }
+
+    // Reparte `cantidad` entre las cuatro columnas que rodean el punto
+    fn sumar_bilineal(&mut self, px: f64, pz: f64, cantidad: f64) {
+        let (cx, cz) = (px.floor() as usize, pz.floor() as usize);
+        let (u, v) = (px - cx as f64, pz - cz as f64);
+        let i = cz * self.ancho + cx;
+        self.alturas[i] += cantidad * (1.0 - u) * (1.0 - v);
+        self.alturas[i + 1] += cantidad * u * (1.0 - v);
+        self.alturas[i + self.ancho] += cantidad * (1.0 - u) * v;
+        self.alturas[i + self.ancho + 1] += cantidad * u * v;
+    }
+}
+
+/// Parámetros de la erosión. Las gotas y las pasadas térmicas marcan cuánto dura y
+/// `erosion`, `deposito` y `fuerza_termica` con qué fuerza actúa.
+#[derive(Clone, Debug)]
+pub struct ConfiguracionErosion {
+    pub gotas_por_columna: f64,    // Gotas de la erosión hidráulica (el total crece con el mapa)
+    pub vida_gota: usize,          // Pasos máximos de cada gota
+    pub inercia: f64,              // 0 = sigue la pendiente, 1 = no cambia de rumbo
+    pub capacidad: f64,            // Sedimento que carga una gota por unidad de caída y velocidad
+    pub capacidad_minima: f64,
+    pub erosion: f64,              // Fracción de la capacidad libre que arranca en cada paso
+    pub deposito: f64,             // Fracción del exceso de sedimento que suelta
+    pub evaporacion: f64,
+    pub gravedad: f64,
+    pub radio: i32,                // Radio de la brocha con que arranca material
+    pub pasadas_termicas: usize,
+    pub talud: f64,                // Desnivel máximo estable entre columnas vecinas
+    pub fuerza_termica: f64,       // Fracción del exceso que se desliza en cada pasada
+}
+
+impl Default for ConfiguracionErosion {
+    fn default() -> Self {
+        Self {
+            gotas_por_columna: 1.0,
+            vida_gota: 30,
+            inercia: 0.05,
+            capacidad: 4.0,
+            capacidad_minima: 0.01,
+            erosion: 0.3,
+            deposito: 0.3,
+            evaporacion: 0.02,
+            gravedad: 4.0,
+            radio: 2,
+            pasadas_termicas: 20,
+            talud: 1.5,
+            fuerza_termica: 0.5,
+        }
+    }
+}
+
+/// Material movido por cada proceso, en bloques
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResumenErosion {
+    pub erosionado: f64,
+    pub depositado: f64,
+    pub deslizado: f64,
+}
+
+/// Erosión hidráulica y después térmica. Con la misma semilla el resultado es el mismo.
+pub fn erosionar(mapa: &mut MapaAlturas, config: &ConfiguracionErosion, semilla: u64) -> ResumenErosion {
+    let mut resumen = erosion_hidraulica(mapa, config, semilla);
+    resumen.deslizado = erosion_termica(mapa, config);
+    resumen
+}
+
+// Pesos de la brocha: decrecen linealmente con la distancia y suman 1
+fn brocha(radio: i32) -> Vec<(i32, i32, f64)> {
+    let mut pesos: Vec<(i32, i32, f64)> = (-radio..=radio)
+        .flat_map(|dz| (-radio..=radio).map(move |dx| (dx, dz)))
+        .map(|(dx, dz)| (dx, dz, (radio as f64 - ((dx * dx + dz * dz) as f64).sqrt()).max(0.0)))
+        .filter(|&(_, _, peso)| peso > 0.0)
+        .collect();
+    let total: f64 = pesos.iter().map(|p| p.2).sum();
+    for peso in &mut pesos {
+        peso.2 /= total;
+    }
+    pesos
+}
+
+/// Erosión hidráulica por partículas (Beyer 2015): cada gota baja por la pendiente,
+/// arranca material donde acelera y lo deja donde frena o cuando ya no puede cargarlo.
+pub fn erosion_hidraulica(mapa: &mut MapaAlturas, config: &ConfiguracionErosion, semilla: u64) -> ResumenErosion {
+    let mut resumen = ResumenErosion::default();
+    if mapa.ancho < 3 || mapa.largo < 3 {
+        return resumen;
+    }
+    let mut rng = StdRng::seed_from_u64(semilla ^ 0xE205_1014);
+    let pesos = brocha(config.radio.max(1));
+    let (ancho, largo) = (mapa.ancho as f64, mapa.largo as f64);
+
+    let gotas = (config.gotas_por_columna * (mapa.ancho * mapa.largo) as f64).round() as usize;
+    for _ in 0..gotas {
+        let mut posicion = (rng.gen_range(0.0..ancho - 1.0), rng.gen_range(0.0..largo - 1.0));
+        let mut direccion = (0.0, 0.0);
+        let (mut velocidad, mut agua, mut sedimento) = (1.0, 1.0, 0.0);
+
+        let mut dentro = true;
+
+        for _ in 0..config.vida_gota {
+            let anterior = posicion;
+            let (cx, cz) = (posicion.0.floor() as usize, posicion.1.floor() as usize);
+            let (altura, gx, gz) = mapa.altura_y_gradiente(posicion.0, posicion.1);
+
+            // Nuevo rumbo: mezcla del anterior con la bajada más empinada
+            direccion = (
+                direccion.0 * config.inercia - gx * (1.0 - config.inercia),
+                direccion.1 * config.inercia - gz * (1.0 - config.inercia),
+            );
+            let largo_direccion = (direccion.0 * direccion.0 + direccion.1 * direccion.1).sqrt();
+            if largo_direccion < 1e-9 {
+                break;
+            }
+            direccion = (direccion.0 / largo_direccion, direccion.1 / largo_direccion);
+            posicion = (posicion.0 + direccion.0, posicion.1 + direccion.1);
+            if posicion.0 < 0.0 || posicion.1 < 0.0 || posicion.0 >= ancho - 1.0 || posicion.1 >= largo - 1.0 {
+                // El sedimento que sale por el borde se pierde
+                dentro = false;
+                break;
+            }
+
+            let desnivel = mapa.altura_y_gradiente(posicion.0, posicion.1).0 - altura;
+            let capacidad = (-desnivel * velocidad * agua * config.capacidad).max(config.capacidad_minima);
+
+            if desnivel > 0.0 || sedimento > capacidad {
+                // Subiendo rellena el hoyo que deja atrás; si no, suelta lo que sobra
+                let soltar = if desnivel > 0.0 {
+                    desnivel.min(sedimento)
+                } else {
+                    (sedimento - capacidad) * config.deposito
+                };
+                sedimento -= soltar;
+                resumen.depositado += soltar;
+                mapa.sumar_bilineal(anterior.0, anterior.1, soltar);
+            } else {
+                // Nunca arranca más de lo que ha bajado, para no cavar pozos
+                let arrancar = ((capacidad - sedimento) * config.erosion).min(-desnivel);
+                for &(dx, dz, peso) in &pesos {
+                    let (x, z) = (cx as i32 + dx, cz as i32 + dz);
+                    if x < 0 || z < 0 || x as usize >= mapa.ancho || z as usize >= mapa.largo {
+                        continue;
+                    }
+                    let celda = &mut mapa.alturas[z as usize * mapa.ancho + x as usize];
+                    let quitado = (arrancar * peso).min(*celda);
+                    *celda -= quitado;
+                    sedimento += quitado;
+                    resumen.erosionado += quitado;
+                }
+            }
+
+            velocidad = (velocidad * velocidad - desnivel * config.gravedad).max(0.0).sqrt();
+            agua *= 1.0 - config.evaporacion;
+        }
+
+        // Al evaporarse la gota deja lo que aún carga
+        if dentro {
+            mapa.sumar_bilineal(posicion.0, posicion.1, sedimento);
+            resumen.depositado += sedimento;
+        }
+    }
+    resumen
+}
+
+/// Erosión térmica: donde el desnivel con una vecina supera el talud, parte del
+/// exceso se desliza hacia ella. Devuelve el material deslizado.
+pub fn erosion_termica(mapa: &mut MapaAlturas, config: &ConfiguracionErosion) -> f64 {
+    let (ancho, largo) = (mapa.ancho, mapa.largo);
+    let mut deslizado = 0.0;
+    let mut cambios = vec![0.0; mapa.alturas.len()];
+
+    for _ in 0..config.pasadas_termicas {
+        cambios.fill(0.0);
+        for z in 0..largo {
+            for x in 0..ancho {
+                let i = z * ancho + x;
+                let altura = mapa.alturas[i];
+                let vecinas: Vec<(usize, f64)> = [(1i32, 0i32), (-1, 0), (0, 1), (0, -1)]
+                    .iter()
+                    .map(|&(dx, dz)| (x as i32 + dx, z as i32 + dz))
+                    .filter(|&(vx, vz)| vx >= 0 && vz >= 0 && (vx as usize) < ancho && (vz as usize) < largo)
+                    .map(|(vx, vz)| vz as usize * ancho + vx as usize)
+                    .map(|j| (j, altura - mapa.alturas[j] - config.talud))
+                    .filter(|&(_, exceso)| exceso > 0.0)
+                    .collect();
+                let (maximo, total) = vecinas
+                    .iter()
+                    .fold((0.0f64, 0.0), |(maximo, total), &(_, exceso)| (maximo.max(exceso), total + exceso));
+                if total <= 0.0 {
+                    continue;
+                }
+
+                // Se mueve la mitad del mayor exceso (así la ladera queda en el talud y
+                // no se invierte), repartida según el exceso de cada vecina
+                let mover = config.fuerza_termica * maximo / 2.0;
+                for (j, exceso) in vecinas {
+                    cambios[j] += mover * exceso / total;
+                }
+                cambios[i] -= mover;
+                deslizado += mover;
+            }
+        }
+        for (altura, cambio) in mapa.alturas.iter_mut().zip(&cambios) {
+            *altura += cambio;
+        }
+    }
+    deslizado
+}
@@ -2,8 +2,24 @@ use nalgebra::{Vector3, Point3};
 use crate::geometria::*;
 use crate::materiales::*;
 use crate::iluminacion::*;
+use crate::entorno::MapaEntorno;
+use crate::mundo::*;
+use crate::mallado::{mallar, MallaVoxel};
+use crate::subsuelo::GeneradorSubsuelo;
+use crate::erosion::ConfiguracionErosion;
+use crate::hidrologia::{Agua, CeldaHidrica, ConfiguracionHidrologia, RedHidrica};
+use crate::terreno::{Bioma, GeneradorTerreno};
+use crate::arboles::{Arbol, Especie};
+use crate::distribucion::{Colocacion, Distribucion, ReglasColocacion};
+use crate::ruido::aleatorio_celda;
 use rand::{thread_rng, Rng};
 
+// Altura en bloques de los mundos de las escenas predefinidas
+const ALTURA_MUNDO: usize = 40;
+// Semilla y columnas (x, z; mínimo y máximo incluidos) del terreno de la escena principal
+pub const SEMILLA_DIORAMA: u64 = 3;
+pub const REGION_DIORAMA: ((i32, i32), (i32, i32)) = ((-25, -25), (24, 24));
+
 pub struct Escena {
     pub objetos: Vec<Box<dyn Figura>>,
     pub luces: Vec<Luz>,
@@ -28,15 +44,53 @@ impl Escena {
         self.luces.push(luz);
     }
     
+    // Reemplaza las luces de la escena (por ejemplo por las de un archivo de escena). Se
+    // conservan las luces de área de los bloques emisivos, que son parte del mundo.
+    pub fn usar_luces(&mut self, luces: Vec<Luz>) {
+        self.luces.retain(|luz| matches!(luz.tipo, TipoLuz::Area { .. }));
+        self.luces.extend(luces);
+    }
+    
+    // Ilumina con un mapa de entorno en lugar del sol y el cielo (el mapa ya los contiene)
+    pub fn usar_entorno(&mut self, mapa: MapaEntorno, intensidad: f64) {
+        self.luces.retain(|luz| !luz.es_cielo() && !matches!(luz.tipo, TipoLuz::Direccional { .. }));
+        self.agregar_luz(Luz::entorno(mapa, intensidad));
+    }
+    
+    // Agrega las caras visibles del mundo, fusionadas, y una luz de área por cada
+    // bloque emisivo. Devuelve la malla para poder informar de su tamaño.
+    pub fn agregar_mundo(&mut self, mundo: &Mundo) -> MallaVoxel {
+        for ((x, y, z), id) in mundo.bloques() {
+            if let Some(material) = mundo.paleta.material(id).filter(|m| m.es_emisivo()) {
+                let minimo = Point3::new(x as f64, y as f64, z as f64);
+                self.agregar_luz(Luz::area(minimo, minimo + Vector3::new(1.0, 1.0, 1.0), material.emision, 1.0));
+            }
+        }
+        
+        let malla = mallar(mundo, true);
+        for cara in &malla.caras {
+            if let Some(material) = mundo.paleta.material(cara.id) {
+                let (minimo, maximo) = cara.limites();
+                self.agregar_objeto(Box::new(Rectangulo::nuevo(minimo, maximo, cara.normal(), material.clone())));
+            }
+        }
+        malla
+    }
+    
     pub fn intersectar(&self, rayo: &Rayo) -> Option<Interseccion> {
+        self.intersectar_con_indice(rayo).map(|(_, interseccion)| interseccion)
+    }
+    
+    // Como `intersectar`, devolviendo también la posición del objeto en `objetos`
+    pub fn intersectar_con_indice(&self, rayo: &Rayo) -> Option<(usize, Interseccion)> {
         let mut interseccion_mas_cercana = None;
         let mut t_minimo = f64::INFINITY;
         
-        for objeto in &self.objetos {
+        for (indice, objeto) in self.objetos.iter().enumerate() {
             if let Some(interseccion) = objeto.intersectar(rayo) {
                 if interseccion.t < t_minimo {
                     t_minimo = interseccion.t;
-                    interseccion_mas_cercana = Some(interseccion);
+                    interseccion_mas_cercana = Some((indice, interseccion));
                 }
             }
         }
@@ -44,37 +98,65 @@ impl Escena {
         interseccion_mas_cercana
     }
     
-    #[allow(dead_code)]
-    pub fn hay_obstruccion(&self, rayo: &Rayo, distancia_maxima: f64) -> bool {
+    // Densidad con la que el muestreo de luces habría elegido el punto golpeado
+    pub fn pdf_luz(&self, origen: &Point3<f64>, interseccion: &Interseccion) -> f64 {
+        self.luces
+            .iter()
+            .map(|luz| luz.pdf(origen, &interseccion.punto, &interseccion.normal))
+            .sum()
+    }
+    
+    // Fracción de luz (por canal) que atraviesa la escena a lo largo del rayo de sombra.
+    // Los materiales transparentes tiñen la luz con su albedo y las hojas la dejan
+    // pasar por los huecos de su textura.
+    pub fn transmitancia(&self, rayo: &Rayo, distancia_maxima: f64) -> Vector3<f64> {
+        let mut transmitancia = Vector3::new(1.0, 1.0, 1.0);
+        
         for objeto in &self.objetos {
             if let Some(interseccion) = objeto.intersectar(rayo) {
-                if interseccion.t < distancia_maxima - 0.001 {
-                    return true;
+                if interseccion.t >= distancia_maxima - 0.001 {
+                    continue;
+                }
+                
+                let material = &interseccion.material;
+                if !material.es_opaco_en(&interseccion.punto, &interseccion.normal) {
+                    continue;
+                }
+                if material.transparencia <= 0.0 {
+                    return Vector3::zeros();
+                }
+                
+                transmitancia = transmitancia.component_mul(&(material.albedo * material.transparencia));
+                if transmitancia.max() < 1e-4 {
+                    return Vector3::zeros();
                 }
             }
         }
-        false
+        
+        transmitancia
     }
 }
 
 // Función para crear un diorama EXACTAMENTE como el proyecto original
-#[allow(dead_code)]
-pub fn crear_diorama() -> Escena {
-    let mut escena = Escena::nueva();
+pub fn crear_mundo_diorama() -> Mundo {
+    let mut mundo = Mundo::nuevo(ALTURA_MUNDO);
     let mut rng = thread_rng();
     
+    let pasto = mundo.paleta.registrar("pasto_diorama", Material::pasto_texturizado());
+    let agua = mundo.paleta.registrar("agua_diorama", Material::agua_texturizada());
+    let tronco = mundo.paleta.registrar("tronco_oscuro", Material::nuevo(Vector3::new(0.4, 0.2, 0.1), 0.0, 0.0, 1.0, 0.9, 0.0));
+    let copa = mundo.paleta.registrar("copa", Material::nuevo(Vector3::new(0.1, 0.6, 0.1), 0.0, 0.0, 1.0, 0.9, 0.0));
+    let flores = [
+        mundo.paleta.registrar("flor_roja", Material::flor_roja()),
+        mundo.paleta.registrar("flor_amarilla", Material::flor_amarilla()),
+        mundo.paleta.registrar("flor_azul", Material::flor_azul()),
+        mundo.paleta.registrar("flor_rosa", Material::flor_rosa()),
+    ];
+    
     let terrain_size = 20;  // Tamaño más pequeño para que todo sea visible
     
     // === TERRENO BASE CON TEXTURA DE PASTO MINECRAFT ===
-    for x in 0..terrain_size {
-        for z in 0..terrain_size {
-            escena.agregar_objeto(Box::new(Cubo::nuevo(
-                Point3::new(x as f64, 0.0, z as f64),
-                1.0,
-                Material::pasto_texturizado()  // Pasto con textura real de Minecraft
-            )));
-        }
-    }
+    mundo.rellenar((0, 0, 0), (terrain_size - 1, 0, terrain_size - 1), pasto);
     
     // === MONTAÑAS GRANDES Y VISIBLES ===
     // Crear montañas en lugares específicos donde la cámara las pueda ver
@@ -90,35 +172,19 @@ pub fn crear_diorama() -> Escena {
         for dx in -1..=1 {
             for dz in -1..=1 {
                 for y in 1..=height {
-                    let material = if y > height - 2 {
-                        Material::nuevo(Vector3::new(0.9, 0.9, 0.9), 0.0, 0.0, 1.0, 0.9, 0.0) // Nieve
-                    } else {
-                        Material::dirt_texturizado() // Tierra con textura real de Minecraft
-                    };
+                    let material = if y > height - 2 { NIEVE } else { TIERRA };
                     
                     let x_pos = (mx + dx).clamp(0, terrain_size-1);
                     let z_pos = (mz + dz).clamp(0, terrain_size-1);
                     
-                    escena.agregar_objeto(Box::new(Cubo::nuevo(
-                        Point3::new(x_pos as f64, y as f64, z_pos as f64),
-                        1.0,
-                        material
-                    )));
+                    mundo.colocar(x_pos, y, z_pos, material);
                 }
             }
         }
     }
     
     // === LAGO CON TEXTURA DE AGUA MINECRAFT ===
-    for x in 8..14 {  // Centro del terreno
-        for z in 8..14 {
-            escena.agregar_objeto(Box::new(Cubo::nuevo(
-                Point3::new(x as f64, 0.5, z as f64),  // Ligeramente elevado
-                1.0,
-                Material::agua_texturizada()  // Agua con textura real de Minecraft
-            )));
-        }
-    }
+    mundo.rellenar((8, 0, 8), (13, 0, 13), agua);  // Centro del terreno
     
     // === ÁRBOLES GRANDES Y MUY VISIBLES ===
     let tree_positions = vec![
@@ -127,24 +193,14 @@ pub fn crear_diorama() -> Escena {
     
     for (x, z) in tree_positions {
         // Tronco del árbol - MÁS ALTO
-        for y in 1..=6 {  // Troncos más altos
-            escena.agregar_objeto(Box::new(Cubo::nuevo(
-                Point3::new(x as f64, y as f64, z as f64),
-                1.0,
-                Material::nuevo(Vector3::new(0.4, 0.2, 0.1), 0.0, 0.0, 1.0, 0.9, 0.0)  // Marrón oscuro
-            )));
-        }
+        mundo.rellenar((x, 1, z), (x, 6, z), tronco);
         
         // Copa del árbol - MÁS GRANDE Y VISIBLE
         for dx in -2i32..=2i32 {
             for dz in -2i32..=2i32 {
                 for dy in 0..=3 {  // Copa más alta
                     if (dx.abs() + dz.abs() + dy) <= 4 {  // Forma piramidal
-                        escena.agregar_objeto(Box::new(Cubo::nuevo(
-                            Point3::new((x as i32 + dx) as f64, (7 + dy) as f64, (z as i32 + dz) as f64),
-                            1.0,
-                            Material::nuevo(Vector3::new(0.1, 0.6, 0.1), 0.0, 0.0, 1.0, 0.9, 0.0)  // Verde oscuro
-                        )));
+                        mundo.colocar(x + dx, 7 + dy, z + dz, copa);
                     }
                 }
             }
@@ -157,20 +213,17 @@ pub fn crear_diorama() -> Escena {
     ];
     
     for (x, z) in flower_positions {
-        let flower_material = match rng.gen_range(0..4) {
-            0 => Material::flor_roja(),
-            1 => Material::flor_amarilla(),
-            2 => Material::flor_azul(),
-            _ => Material::flor_rosa(),
-        };
-        
-        escena.agregar_objeto(Box::new(Cubo::nuevo(
-            Point3::new(x as f64, 1.0, z as f64),
-            0.8,
-            flower_material
-        )));
+        mundo.colocar_si_aire(x, 1, z, flores[rng.gen_range(0..flores.len())]);
     }
     
+    mundo
+}
+
+#[allow(dead_code)]
+pub fn crear_diorama() -> Escena {
+    let mut escena = Escena::nueva();
+    escena.agregar_mundo(&crear_mundo_diorama());
+    
     // Configurar iluminación
     for luz in crear_iluminacion_minecraft() {
         escena.agregar_luz(luz);
@@ -179,350 +232,160 @@ pub fn crear_diorama() -> Escena {
     escena
 }
 
-// ====================== MUNDO MINECRAFT MASIVO CON TEXTURAS ======================
+// ====================== ESCENA SIMPLE PARA DEBUG ======================
 
-#[allow(dead_code)]
-pub fn crear_escena_minecraft_masiva() -> Escena {
-    let mut escena = Escena::nueva();
-    
-    // ====================== CONFIGURACIÓN DEL MUNDO PEQUEÑO ======================
-    let size = 10;   // Mundo pequeño pero visible 10x10
-    let spacing = 3.0;  // Espaciado grande para ver bien
-    let centro = size as f64 / 2.0;
-    
-    println!("🌍 Generando mundo Minecraft PEQUEÑO {}x{} con todas las texturas...", size, size);
-    
-    // ====================== SISTEMA DE BIOMAS ======================
-    
-    // SUELO BASE CON BIOMAS
-    for x in 0..size {
-        for z in 0..size {
-            let pos_x = (x as f64 - centro) * spacing;
-            let pos_z = (z as f64 - centro) * spacing;
-            let distancia_centro = ((pos_x / spacing).powi(2) + (pos_z / spacing).powi(2)).sqrt();
-            
-            // Determinar material según distancia del centro (ajustado para 10x10)
-            let material = if distancia_centro < 2.0 {
-                // BIOMA CENTRAL - Pasto verde
-                Material::grass_top_texturizado()
-            } else if distancia_centro < 3.5 {
-                // BIOMA INTERMEDIO - Tierra
-                Material::dirt_texturizado()
-            } else {
-                // BIOMA EXTREMO - Piedra
-                Material::stone_texturizado()
-            };
-            
-            let suelo = Box::new(Cubo::con_limites(
-                Point3::new(pos_x - 1.0, -1.0, pos_z - 1.0),
-                Point3::new(pos_x + 1.0, 0.0, pos_z + 1.0),
-                material
-            ));
-            escena.agregar_objeto(suelo);
-        }
-    }
-    
-    // ====================== SISTEMA MONTAÑOSO MASIVO ======================
-    
-    for x in 0..size {
-        for z in 0..size {
-            let pos_x = (x as f64 - centro) * spacing;
-            let pos_z = (z as f64 - centro) * spacing;  
-            let distancia_centro = ((pos_x / spacing).powi(2) + (pos_z / spacing).powi(2)).sqrt();
-            
-            // MONTAÑAS PRINCIPALES (perímetro exterior)
-            if distancia_centro > 4.0 && distancia_centro < 6.0 {
-                let altura = (4.0 + (x + z) as f64 * 0.2) % 8.0 + 3.0;
-                
-                let material = if (x + z) % 3 == 0 {
-                    Material::stone_texturizado()
-                } else if (x + z) % 3 == 1 {
-                    Material::cobblestone_texturizado()
-                } else {
-                    Material::gravel_texturizado()
-                };
-                
-                let montaña = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x - 1.0, 0.0, pos_z - 1.0),
-                    Point3::new(pos_x + 1.0, altura, pos_z + 1.0),
-                    material
-                ));
-                escena.agregar_objeto(montaña);
-            }
-            
-            // COLINAS INTERMEDIAS
-            if distancia_centro > 3.0 && distancia_centro < 4.0 && (x + z) % 2 == 0 {
-                let altura = (2.0 + (x * z) as f64 * 0.05) % 4.0 + 1.5;
-                
-                let colina = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x - 1.0, 0.0, pos_z - 1.0),
-                    Point3::new(pos_x + 1.0, altura, pos_z + 1.0),
-                    Material::dirt_texturizado()
-                ));
-                escena.agregar_objeto(colina);
-            }
-        }
-    }
-    
-    // ====================== LAGOS Y RÍOS ======================
-    
-    // LAGO CENTRAL PRINCIPAL
-    let radio_lago = 1.5;
-    for x in 0..size {
-        for z in 0..size {
-            let pos_x = (x as f64 - centro) * spacing;
-            let pos_z = (z as f64 - centro) * spacing;
-            let distancia_centro = ((pos_x / spacing).powi(2) + (pos_z / spacing).powi(2)).sqrt();
-            
-            if distancia_centro < radio_lago {
-                let profundidad = if distancia_centro < 3.0 { 1.2 } else { 0.8 };
-                
-                let agua = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x - 1.0, 0.0, pos_z - 1.0),
-                    Point3::new(pos_x + 1.0, profundidad, pos_z + 1.0),
-                    Material::water_texturizado()
-                ));
-                escena.agregar_objeto(agua);
-            }
-        }
-    }
-    
-    // LAGOS SECUNDARIOS
-    let lagos_secundarios = [(20.0, 20.0, 4.0), (-25.0, 15.0, 3.0), (15.0, -30.0, 3.5), (-20.0, -20.0, 4.0)];
-    for (lago_x, lago_z, radio) in &lagos_secundarios {
-        for x in 0..size {
-            for z in 0..size {
-                let pos_x = (x as f64 - centro) * spacing;
-                let pos_z = (z as f64 - centro) * spacing;
-                let dist_lago = ((pos_x - lago_x).powi(2) + (pos_z - lago_z).powi(2)).sqrt();
-                
-                if dist_lago < *radio {
-                    let agua = Box::new(Cubo::con_limites(
-                        Point3::new(pos_x - 1.0, 0.0, pos_z - 1.0),
-                        Point3::new(pos_x + 1.0, 0.6, pos_z + 1.0),
-                        Material::agua_texturizada()
-                    ));
-                    escena.agregar_objeto(agua);
+/// Mundo del diorama con lo que se generó en él. La ventana raylib, su vista previa en
+/// CPU y las secuencias giratorias muestran este mismo mundo.
+pub struct Diorama {
+    pub mundo: Mundo,
+    pub nivel_mar: i32,
+    pub arboles: Vec<(Posicion, Arbol)>,    // Base del tronco y árbol plantado
+    pub rocas: Vec<Colocacion>,             // Sitios de rocas y flores (solo en la vista raylib)
+    pub flores: Vec<Colocacion>,
+    pub puente: Option<Posicion>,  // Centro del tablero del puente
+}
+
+// Pone piedra luminosa en algunos suelos de cueva (bajo la superficie y sin agua) para
+// que se vea la luz de los bloques. Devuelve cuántas puso.
+fn colocar_lamparas(mundo: &mut Mundo, minimo: (i32, i32), maximo: (i32, i32), semilla: u64) -> usize {
+    let mut lamparas = 0;
+    for x in minimo.0..=maximo.0 {
+        for z in minimo.1..=maximo.1 {
+            let techo = mundo.superficie(x, z).unwrap_or(0) - 1;
+            for y in 1..techo {
+                let suelo = mundo.obtener(x, y - 1, z);
+                if mundo.es_aire(x, y, z)
+                    && !matches!(suelo, AIRE | AGUA | LAVA)
+                    && aleatorio_celda(semilla.wrapping_add(y as u64), x, z) < 0.03
+                {
+                    mundo.colocar(x, y, z, PIEDRA_LUMINOSA);
+                    lamparas += 1;
                 }
             }
         }
     }
-    
-    // ====================== ÁRBOLES SIMPLES ======================
-    
-    // Solo algunos árboles simples para mundo pequeño
-    for i in 0..5 {
-        let x = (i + 2) % size;
-        let z = (i + 3) % size;
-        let pos_x = (x as f64 - centro) * spacing;
-        let pos_z = (z as f64 - centro) * spacing;
-        let distancia_centro = ((pos_x / spacing).powi(2) + (pos_z / spacing).powi(2)).sqrt();
-        
-        if distancia_centro > 2.0 && distancia_centro < 3.5 {
-            // Tronco simple
-            let tronco = Box::new(Cubo::con_limites(
-                Point3::new(pos_x - 0.3, 0.0, pos_z - 0.3),
-                Point3::new(pos_x + 0.3, 3.0, pos_z + 0.3),
-                Material::oak_log_texturizado()
-            ));
-            escena.agregar_objeto(tronco);
-            
-            // Hojas simples
-            let hojas = Box::new(Cubo::con_limites(
-                Point3::new(pos_x - 1.0, 2.5, pos_z - 1.0),
-                Point3::new(pos_x + 1.0, 4.0, pos_z + 1.0),
-                Material::oak_leaves_texturizado()
-            ));
-            escena.agregar_objeto(hojas);
-        }
-    }
-    
-    // ====================== DEPÓSITOS MINERALES ======================
-    
-    // CARBÓN en montañas
-    for i in 0..80 {
-        let x = (i * 13 + 40) % size;
-        let z = (i * 17 + 35) % size;
-        let pos_x = (x as f64 - centro) * spacing;
-        let pos_z = (z as f64 - centro) * spacing;
-        let distancia_centro = ((pos_x / spacing).powi(2) + (pos_z / spacing).powi(2)).sqrt();
-        
-        if distancia_centro > 30.0 && distancia_centro < 42.0 {
-            let mineral = Box::new(Cubo::con_limites(
-                Point3::new(pos_x - 0.5, 0.0, pos_z - 0.5),
-                Point3::new(pos_x + 0.5, 1.5, pos_z + 0.5),
-                Material::coal_ore_texturizado()
-            ));
-            escena.agregar_objeto(mineral);
-        }
-    }
-    
-    // HIERRO en montañas profundas
-    for i in 0..50 {
-        let x = (i * 19 + 20) % size;
-        let z = (i * 23 + 45) % size;
-        let pos_x = (x as f64 - centro) * spacing;
-        let pos_z = (z as f64 - centro) * spacing;
-        let distancia_centro = ((pos_x / spacing).powi(2) + (pos_z / spacing).powi(2)).sqrt();
-        
-        if distancia_centro > 35.0 && distancia_centro < 43.0 {
-            let mineral = Box::new(Cubo::con_limites(
-                Point3::new(pos_x - 0.4, 0.0, pos_z - 0.4),
-                Point3::new(pos_x + 0.4, 2.0, pos_z + 0.4),
-                Material::iron_ore_texturizado()
-            ));
-            escena.agregar_objeto(mineral);
-        }
-    }
-    
-    // Luz principal optimizada para mundo grande
-    escena.agregar_luz(Luz::puntual(
-        Point3::new(50.0, 80.0, 50.0),
-        Vector3::new(1.0, 1.0, 1.0),
-        200.0
-    ));
-    
-    // Luz secundaria
-    escena.agregar_luz(Luz::puntual(
-        Point3::new(-50.0, 60.0, -50.0),
-        Vector3::new(0.8, 0.9, 1.0),
-        150.0
-    ));
-    
-    println!("✅ Mundo Minecraft MASIVO generado:");
-    println!("   🌍 Tamaño: {}x{} = {} bloques", size, size, size * size);
-    println!("   🏔️ Montañas perimetrales con 3 tipos de piedra");
-    println!("   🌊 5 lagos (1 central + 4 secundarios)");
-    println!("   🌲 450 árboles (roble, abedul, jungla)");
-    println!("   ⛏️ 130 depósitos minerales (carbón + hierro)");
-    println!("   🎨 Usa todas las 19 texturas disponibles");
-    
-    escena
+    lamparas
 }
 
-// ====================== ESCENA SIMPLE PARA DEBUG ======================
+// Tiende un puente de troncos sobre el tramo de río con más caudal que se pueda
+// cruzar, de orilla a orilla y perpendicular a la corriente. Devuelve el centro del tablero.
+fn tender_puente(mundo: &mut Mundo, red: &RedHidrica) -> Option<Posicion> {
+    let tramo = |(i, celda): (usize, &CeldaHidrica)| {
+        let (x, z) = red.posicion(i);
+        let (dx, dz) = red.posicion(celda.destino?);
+        let (px, pz) = (dz - z, x - dx);
+        // Avanzar hacia cada lado hasta salir del agua
+        let orilla = |signo: i32| {
+            (1..=4).map(|paso| (x + px * paso * signo, z + pz * paso * signo))
+                .find(|&(ox, oz)| red.celda(ox, oz).is_some_and(|c| c.agua == Agua::Seca))
+        };
+        Some((celda.acumulacion, (x, z), orilla(1)?, orilla(-1)?))
+    };
+    let (_, (x, z), a, b) = red
+        .celdas
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.cauce && c.agua == Agua::Rio)
+        .filter_map(tramo)
+        .max_by_key(|&(acumulacion, ..)| acumulacion)?;
+    
+    let altura = |(ox, oz): (i32, i32)| red.celda(ox, oz).map_or(0, |c| c.superficie);
+    let tablero = altura(a).max(altura(b)) + 1;
+    mundo.rellenar((a.0.min(b.0), tablero, a.1.min(b.1)), (a.0.max(b.0), tablero, a.1.max(b.1)), TRONCO_ROBLE);
+    Some((x, tablero, z))
+}
 
-pub fn crear_escena_minecraft_simple() -> Escena {
-    let mut escena = Escena::nueva();
+pub fn crear_mundo_minecraft_simple() -> Diorama {
+    let mut mundo = Mundo::nuevo(ALTURA_MUNDO);
     
     println!("🌍 Generando DIORAMA MINECRAFT COMPLETO...");
     
-    // TERRENO NATURAL 30x30
-    let size = 30;
-    let mut alturas = vec![vec![0i32; size]; size];
-    
-    // Generar alturas con ruido natural
-    for x in 0..size {
-        for z in 0..size {
-            let fx = x as f64 * 0.1;
-            let fz = z as f64 * 0.1;
-            
-            let altura1 = (fx * 0.8).sin() * (fz * 0.6).cos() * 3.0;
-            let altura2 = (fx * 0.3).sin() * (fz * 0.4).sin() * 2.0;
-            let altura3 = (fx * 0.15).cos() * (fz * 0.2).cos() * 4.0;
-            
-            let altura_final = (altura1 + altura2 + altura3 + 5.0) as i32;
-            alturas[x][z] = altura_final.max(1).min(12);
-        }
-    }
-    
-    // COLOCAR TERRENO
-    for x in 0..size {
-        for z in 0..size {
-            let altura_max = alturas[x][z];
-            
-            for y in 0..altura_max {
-                let pos_x = x as f64 - size as f64 / 2.0;
-                let pos_z = z as f64 - size as f64 / 2.0;
-                let pos_y = y as f64;
-                
-                let material = if y == altura_max - 1 {
-                    Material::grass_top_texturizado()
-                } else if y >= altura_max - 3 {
-                    Material::dirt_texturizado()
-                } else {
-                    Material::stone_texturizado()
-                };
-                
-                let bloque = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x, pos_y, pos_z),
-                    Point3::new(pos_x + 1.0, pos_y + 1.0, pos_z + 1.0),
-                    material
-                ));
-                escena.agregar_objeto(bloque);
-            }
-        }
-    }
-    
-    // ÁRBOLES
-    let posiciones_arboles = [
-        (8, 8), (20, 12), (15, 22), (25, 25), (5, 18), 
-        (22, 5), (12, 25), (18, 15), (7, 25), (25, 8)
-    ];
-    
-    for &(tree_x, tree_z) in &posiciones_arboles {
-        if tree_x < size && tree_z < size {
-            let altura_base = alturas[tree_x][tree_z];
-            let pos_x = tree_x as f64 - size as f64 / 2.0;
-            let pos_z = tree_z as f64 - size as f64 / 2.0;
-            
-            // Tronco
-            let altura_tronco = 4 + (tree_x + tree_z) % 3;
-            for y in 0..altura_tronco {
-                let tronco = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x, altura_base as f64 + y as f64, pos_z),
-                    Point3::new(pos_x + 1.0, altura_base as f64 + y as f64 + 1.0, pos_z + 1.0),
-                    Material::oak_log_texturizado()
-                ));
-                escena.agregar_objeto(tronco);
-            }
-            
-            // Hojas
-            let base_hojas = altura_base as f64 + altura_tronco as f64 - 1.0;
-            for dx in -2..=2 {
-                for dz in -2..=2 {
-                    for dy in 0..3 {
-                        let distancia = (dx * dx + dz * dz) as f64;
-                        if distancia <= 4.0 && (distancia <= 2.0 || dy < 2) {
-                            let hoja = Box::new(Cubo::con_limites(
-                                Point3::new(pos_x + dx as f64, base_hojas + dy as f64, pos_z + dz as f64),
-                                Point3::new(pos_x + dx as f64 + 1.0, base_hojas + dy as f64 + 1.0, pos_z + dz as f64 + 1.0),
-                                Material::oak_leaves_texturizado()
-                            ));
-                            escena.agregar_objeto(hoja);
-                        }
-                    }
-                }
+    // TERRENO NATURAL con ruido fractal y biomas, erosionado por la lluvia
+    let (minimo, maximo) = REGION_DIORAMA;
+    let generador = GeneradorTerreno::nuevo(SEMILLA_DIORAMA);
+    let nivel_mar = generador.config.nivel_mar;
+    let erosion = generador.generar_erosionado(&mut mundo, minimo, maximo, &ConfiguracionErosion::default());
+    println!("   ⛰️ Erosión: {:.0} bloques arrastrados, {:.0} depositados y {:.0} deslizados",
+             erosion.erosionado, erosion.depositado, erosion.deslizado);
+    
+    // Pequeño pozo de lava hundido en la superficie
+    for x in 6..9 {
+        for z in 4..6 {
+            if let Some(y) = mundo.superficie(x, z) {
+                mundo.colocar(x, y, z, LAVA);
             }
         }
     }
     
-    // LAGO
-    let centro_lago_x = size / 4;
-    let centro_lago_z = size / 4;
-    let radio_lago = 4;
-    
-    for x in centro_lago_x - radio_lago..centro_lago_x + radio_lago {
-        for z in centro_lago_z - radio_lago..centro_lago_z + radio_lago {
-            if x < size && z < size {
-                let dx = x as i32 - centro_lago_x as i32;
-                let dz = z as i32 - centro_lago_z as i32;
-                let distancia = ((dx * dx + dz * dz) as f64).sqrt();
-                
-                if distancia <= radio_lago as f64 {
-                    let pos_x = x as f64 - size as f64 / 2.0;
-                    let pos_z = z as f64 - size as f64 / 2.0;
-                    let altura_agua = (alturas[x][z] - 2).max(1);
-                    
-                    let agua = Box::new(Cubo::con_limites(
-                        Point3::new(pos_x, altura_agua as f64, pos_z),
-                        Point3::new(pos_x + 1.0, altura_agua as f64 + 1.0, pos_z + 1.0),
-                        Material::water_texturizado()
-                    ));
-                    escena.agregar_objeto(agua);
-                }
-            }
-        }
+    // RÍOS Y LAGOS: el agua baja por la pendiente y llena las depresiones
+    let red = RedHidrica::calcular(&mundo, minimo, maximo, nivel_mar, ConfiguracionHidrologia::default());
+    let hidrologia = red.aplicar(&mut mundo);
+    
+    // CUEVAS Y MINERALES bajo el terreno, con algunas lámparas en el suelo de las cuevas
+    let subsuelo = GeneradorSubsuelo::nuevo(SEMILLA_DIORAMA)
+        .generar(&mut mundo, minimo, maximo);
+    println!("   ⛏️ Subsuelo: {} bloques excavados, {} vetas de mineral ({} bloques)",
+             subsuelo.excavados, subsuelo.grupos_mineral, subsuelo.bloques_mineral);
+    let lamparas = colocar_lamparas(&mut mundo, minimo, maximo, SEMILLA_DIORAMA);
+    
+    // Altura sobre la que se construye; las entradas de las cuevas pueden haberla bajado
+    let base = |mundo: &Mundo, x: i32, z: i32| mundo.superficie(x, z).map_or(0, |y| y + 1);
+    let base_casa = base(&mundo, 0, -9);
+    let base_torre = base(&mundo, -10, 5);
+    
+    // Sitios de la casa y la torre, que se construyen después: nada de lo que se reparte
+    // los pisa
+    let mut distribucion = Distribucion::nueva(&mundo, &generador, (minimo.0 + 2, minimo.1 + 2), (maximo.0 - 2, maximo.1 - 2));
+    distribucion.ocupar(9.5, -10.5, 3.0);
+    distribucion.ocupar(-10.0, 5.0, 1.0);
+    
+    // ÁRBOLES en un disco de Poisson, fuera de las playas; el bioma de la superficie ya
+    // erosionada decide cuántos crecen y de qué especies.
+    // Cada punto del disco cubre unas distancia² columnas, así que la densidad del bioma
+    // por columna se convierte en la probabilidad de conservar el punto.
+    let reglas_arboles = ReglasColocacion {
+        distancia_minima: 3.0,
+        holgura: 1.5,
+        ..ReglasColocacion::default()
+    };
+    let area_punto = reglas_arboles.distancia_minima.powi(2);
+    let sitios_arboles: Vec<Colocacion> = distribucion
+        .colocar(&reglas_arboles, SEMILLA_DIORAMA.wrapping_add(2))
+        .into_iter()
+        .filter(|sitio| sitio.y > nivel_mar)
+        .filter(|sitio| aleatorio_celda(SEMILLA_DIORAMA, sitio.x, sitio.z) < sitio.bioma.densidad_vegetacion() * area_punto)
+        .take(35)
+        .collect();
+    
+    // ROCAS Y FLORES entre los árboles
+    let reglas_rocas = ReglasColocacion {
+        distancia_minima: 5.0,
+        holgura: 2.0,
+        maximo: 20,
+        pendiente_maxima: 3,
+        ..ReglasColocacion::default()
+    };
+    let reglas_flores = ReglasColocacion {
+        distancia_minima: 2.0,
+        holgura: 0.5,
+        maximo: 50,
+        pendiente_maxima: 1,
+        biomas: vec![Bioma::Llanura, Bioma::Bosque, Bioma::Cerezal],
+        ..ReglasColocacion::default()
+    };
+    let rocas = distribucion.colocar(&reglas_rocas, SEMILLA_DIORAMA.wrapping_add(3));
+    let flores = distribucion.colocar(&reglas_flores, SEMILLA_DIORAMA.wrapping_add(4));
+    
+    // Los árboles se plantan al final para que su copa no cuente como suelo
+    let mut arboles = Vec::new();
+    for sitio in &sitios_arboles {
+        let base = (sitio.x, sitio.y + 1, sitio.z);
+        let azar = aleatorio_celda(SEMILLA_DIORAMA.wrapping_add(1), base.0, base.2);
+        let especie = Especie::para_bioma(sitio.bioma, azar);
+        let columna = ((base.0 - minimo.0) as u64) * (maximo.1 - minimo.1 + 1) as u64 + (base.2 - minimo.1) as u64;
+        let arbol = especie.generar(SEMILLA_DIORAMA.wrapping_mul(1_000_003).wrapping_add(columna));
+        arbol.plantar(&mut mundo, base);
+        arboles.push((base, arbol));
     }
     
     // ESTRUCTURAS ADICIONALES
@@ -531,194 +394,84 @@ pub fn crear_escena_minecraft_simple() -> Escena {
     for x in 0..4 {
         for z in 0..4 {
             for y in 0..3 {
-                let pos_x = (x as f64) + 8.0;
-                let pos_z = (z as f64) - 12.0;
-                
                 let material = if y == 0 {
-                    Material::stone_texturizado()
+                    PIEDRA
                 } else if y < 2 && (x == 0 || x == 3 || z == 0 || z == 3) {
-                    Material::oak_log_texturizado()
+                    TRONCO_ROBLE
                 } else if y == 2 {
-                    Material::oak_leaves_texturizado()
+                    HOJAS_ROBLE
                 } else {
                     continue;
                 };
                 
-                let bloque = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x, alturas[15][6] as f64 + y as f64, pos_z),
-                    Point3::new(pos_x + 1.0, alturas[15][6] as f64 + y as f64 + 1.0, pos_z + 1.0),
-                    material
-                ));
-                escena.agregar_objeto(bloque);
+                mundo.colocar(x + 8, base_casa + y, z - 12, material);
             }
         }
     }
     
+    // Piedra luminosa dentro de la casa
+    mundo.colocar(9, base_casa + 1, -11, PIEDRA_LUMINOSA);
+    
     // Torre alta
     for y in 0..8 {
-        let torre = Box::new(Cubo::con_limites(
-            Point3::new(-10.0, alturas[5][20] as f64 + y as f64, 5.0),
-            Point3::new(-9.0, alturas[5][20] as f64 + y as f64 + 1.0, 6.0),
-            if y < 6 { Material::stone_texturizado() } else { Material::oak_log_texturizado() }
-        ));
-        escena.agregar_objeto(torre);
+        mundo.colocar(-10, base_torre + y, 5, if y < 6 { PIEDRA } else { TRONCO_ROBLE });
     }
     
-    // Puente sobre el lago
-    for x in -2..3 {
-        let puente = Box::new(Cubo::con_limites(
-            Point3::new(x as f64, 3.0, -5.0),
-            Point3::new(x as f64 + 1.0, 4.0, -4.0),
-            Material::oak_log_texturizado()
-        ));
-        escena.agregar_objeto(puente);
-    }
+    // Faro de piedra luminosa en la punta de la torre
+    mundo.colocar(-10, base_torre + 8, 5, PIEDRA_LUMINOSA);
+    
+    // Puente sobre el río principal
+    let puente = tender_puente(&mut mundo, &red);
     
     println!("✅ DIORAMA MINECRAFT COMPLETO generado:");
-    println!("   🏞️ Terreno natural 30x30 con colinas");
-    println!("   🌳 10 árboles completos con hojas");
-    println!("   🌊 1 lago natural");
+    println!("   🏞️ Terreno natural {}x{} con biomas (semilla {})",
+             maximo.0 - minimo.0 + 1, maximo.1 - minimo.1 + 1, SEMILLA_DIORAMA);
+    let especies: Vec<String> = Especie::todas()
+        .iter()
+        .map(|especie| (especie.nombre, arboles.iter().filter(|(_, arbol)| arbol.especie == especie.nombre).count()))
+        .filter(|&(_, cuantos)| cuantos > 0)
+        .map(|(nombre, cuantos)| format!("{} {}", cuantos, nombre))
+        .collect();
+    println!("   🌳 {} árboles ({}), {} rocas y {} flores", arboles.len(), especies.join(", "), rocas.len(), flores.len());
+    println!("   🌊 {} ríos ({} columnas) y {} lagos ({} columnas) con {} columnas de orilla",
+             hidrologia.rios, hidrologia.columnas_rio, hidrologia.lagos,
+             hidrologia.columnas_lago, hidrologia.columnas_orilla);
+    println!("   💡 {} lámparas en las cuevas", lamparas);
     println!("   🏠 1 casa pequeña");
-    println!("   🗼 1 torre alta");
-    println!("   🌉 1 puente");
-    println!("   🧱 {} objetos totales", escena.objetos.len());
-    
-    let size = 10; // Mundo más pequeño para debug
-    let mut alturas = vec![vec![0i32; size]; size]; // Mapa de alturas
-    
-    // GENERAR TERRENO ORGÁNICO CON RUIDO
-    for x in 0..size {
-        for z in 0..size {
-            // Múltiples ondas de ruido para terreno natural
-            let fx = x as f64 * 0.1;
-            let fz = z as f64 * 0.1;
-            
-            let altura1 = (fx * 0.8).sin() * (fz * 0.6).cos() * 3.0;
-            let altura2 = (fx * 0.3).sin() * (fz * 0.4).sin() * 2.0;
-            let altura3 = (fx * 0.15).cos() * (fz * 0.2).cos() * 4.0;
-            
-            let altura_final = (altura1 + altura2 + altura3 + 5.0) as i32;
-            alturas[x][z] = altura_final.max(1).min(15);
-        }
+    println!("   🗼 1 torre alta con faro de piedra luminosa");
+    println!("   🌋 1 pozo de lava");
+    if puente.is_some() {
+        println!("   🌉 1 puente");
     }
-    
-    // COLOCAR BLOQUES DE TERRENO INDIVIDUALES
-    for x in 0..size {
-        for z in 0..size {
-            let altura_max = alturas[x][z];
-            
-            for y in 0..altura_max {
-                let pos_x = x as f64 - size as f64 / 2.0;
-                let pos_z = z as f64 - size as f64 / 2.0;
-                let pos_y = y as f64;
-                
-                let material = if y == altura_max - 1 {
-                    // Superficie: grass
-                    Material::grass_top_texturizado()
-                } else if y >= altura_max - 3 {
-                    // Subsuelo: dirt
-                    Material::dirt_texturizado()
-                } else {
-                    // Profundidad: stone
-                    Material::stone_texturizado()
-                };
-                
-                let bloque = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x, pos_y, pos_z),
-                    Point3::new(pos_x + 1.0, pos_y + 1.0, pos_z + 1.0),
-                    material
-                ));
-                escena.agregar_objeto(bloque);
-            }
-        }
+    println!("   🧱 {} bloques en {} chunks", mundo.numero_bloques(), mundo.chunks().count());
+    
+    Diorama {
+        mundo,
+        nivel_mar,
+        arboles,
+        rocas,
+        flores,
+        puente,
     }
-    
-    // COLOCAR ÁRBOLES DE BLOQUES INDIVIDUALES
-    let posiciones_arboles = [
-        (15, 15), (25, 20), (18, 30), (32, 12), (8, 25), 
-        (28, 28), (12, 8), (35, 35), (5, 15), (22, 25)
-    ];
-    
-    for &(tree_x, tree_z) in &posiciones_arboles {
-        if tree_x < size && tree_z < size {
-            let altura_base = alturas[tree_x][tree_z];
-            let pos_x = tree_x as f64 - size as f64 / 2.0;
-            let pos_z = tree_z as f64 - size as f64 / 2.0;
-            
-            // Tronco (4-6 bloques de altura)
-            let altura_tronco = 4 + (tree_x + tree_z) % 3;
-            for y in 0..altura_tronco {
-                let tronco = Box::new(Cubo::con_limites(
-                    Point3::new(pos_x, altura_base as f64 + y as f64, pos_z),
-                    Point3::new(pos_x + 1.0, altura_base as f64 + y as f64 + 1.0, pos_z + 1.0),
-                    Material::oak_log_texturizado()
-                ));
-                escena.agregar_objeto(tronco);
-            }
-            
-            // Hojas (corona 5x5x3)
-            let base_hojas = altura_base as f64 + altura_tronco as f64 - 1.0;
-            for dx in -2..=2 {
-                for dz in -2..=2 {
-                    for dy in 0..3 {
-                        // Forma más natural de la corona
-                        let distancia = (dx * dx + dz * dz) as f64;
-                        if distancia <= 4.0 && (distancia <= 2.0 || dy < 2) {
-                            let hoja = Box::new(Cubo::con_limites(
-                                Point3::new(pos_x + dx as f64, base_hojas + dy as f64, pos_z + dz as f64),
-                                Point3::new(pos_x + dx as f64 + 1.0, base_hojas + dy as f64 + 1.0, pos_z + dz as f64 + 1.0),
-                                Material::oak_leaves_texturizado()
-                            ));
-                            escena.agregar_objeto(hoja);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    // LAGO NATURAL CON BLOQUES DE AGUA INDIVIDUALES
-    let centro_lago_x = size / 4;
-    let centro_lago_z = size / 4;
-    let radio_lago = 6;
-    
-    for x in centro_lago_x - radio_lago..centro_lago_x + radio_lago {
-        for z in centro_lago_z - radio_lago..centro_lago_z + radio_lago {
-            if x < size && z < size {
-                let dx = x as i32 - centro_lago_x as i32;
-                let dz = z as i32 - centro_lago_z as i32;
-                let distancia = ((dx * dx + dz * dz) as f64).sqrt();
-                
-                if distancia <= radio_lago as f64 {
-                    let pos_x = x as f64 - size as f64 / 2.0;
-                    let pos_z = z as f64 - size as f64 / 2.0;
-                    let altura_agua = (alturas[x][z] - 2).max(1);
-                    
-                    let agua = Box::new(Cubo::con_limites(
-                        Point3::new(pos_x, altura_agua as f64, pos_z),
-                        Point3::new(pos_x + 1.0, altura_agua as f64 + 1.0, pos_z + 1.0),
-                        Material::water_texturizado()
-                    ));
-                    escena.agregar_objeto(agua);
-                }
-            }
-        }
-    }
-    
-    // Agregar iluminación
-    for luz in crear_iluminacion_minecraft() {
-        escena.agregar_luz(luz);
-    }
-    
-    println!("✅ Paisaje de Minecraft REAL generado:");
-    println!("   �️ Terreno orgánico 40x40 con colinas y valles");
-    println!("   � Superficie de grass con dirt y stone por capas");
-    println!("   🌳 10 árboles individuales con troncos y hojas naturales");
-    println!("   🌊 1 lago natural formado por bloques de agua");
-    println!("   🧱 {} objetos totales", escena.objetos.len());
-    
-    // Agregar iluminación
-    for luz in crear_iluminacion_minecraft() {
+}
+
+// Escena del ray tracer con los bloques del mundo (tal como esté: simulado o cortado),
+// el sol, el cielo y el farol que alumbra el puente desde arriba
+pub fn crear_escena_diorama(mundo: &Mundo, puente: Option<Posicion>) -> Escena {
+    let mut escena = Escena::nueva();
+    escena.agregar_mundo(mundo);
+    
+    let config = ConfiguracionIluminacion {
+        faroles: puente
+            .map(|(x, y, z)| Farol::nuevo(
+                Point3::new(x as f64 + 0.5, y as f64 + 5.0, z as f64 + 0.5),
+                Vector3::new(0.0, -1.0, 0.0),
+            ))
+            .into_iter()
+            .collect(),
+        ..ConfiguracionIluminacion::default()
+    };
+    for luz in crear_iluminacion_minecraft_con(&config) {
         escena.agregar_luz(luz);
     }
     
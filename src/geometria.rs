@@ -0,0 +1,80 @@
+use nalgebra::{Vector3, Point3};
+use crate::materiales::Material;
+
+#[derive(Clone, Copy)]
+pub struct Rayo {
+    pub origen: Point3<f64>,
+    pub direccion: Vector3<f64>,  // Siempre normalizada
+}
+
+impl Rayo {
+    pub fn new(origen: Point3<f64>, direccion: Vector3<f64>) -> Self {
+        Self {
+            origen,
+            direccion: direccion.normalize(),
+        }
+    }
+
+    pub fn punto_en(&self, t: f64) -> Point3<f64> {
+        self.origen + self.direccion * t
+    }
+}
+
+pub struct Interseccion {
+    pub t: f64,                  // Distancia a lo largo del rayo
+    pub punto: Point3<f64>,      // Punto de impacto
+    pub normal: Vector3<f64>,    // Normal exterior de la cara golpeada
+    pub material: Material,
+}
+
+pub trait Figura {
+    fn intersectar(&self, rayo: &Rayo) -> Option<Interseccion>;
+}
+
+// Rectángulo alineado a los ejes con una sola cara visible (caras de la malla de vóxeles)
+pub struct Rectangulo {
+    pub eje: usize,               // Eje perpendicular al plano
+    pub minimo: Point3<f64>,      // Esquinas; coinciden en la coordenada `eje`
+    pub maximo: Point3<f64>,
+    pub normal: Vector3<f64>,
+    pub material: Material,
+}
+
+impl Rectangulo {
+    pub fn nuevo(minimo: Point3<f64>, maximo: Point3<f64>, normal: Vector3<f64>, material: Material) -> Self {
+        Self {
+            eje: normal.iamax(),
+            minimo,
+            maximo,
+            normal,
+            material,
+        }
+    }
+}
+
+impl Figura for Rectangulo {
+    fn intersectar(&self, rayo: &Rayo) -> Option<Interseccion> {
+        let eje = self.eje;
+        if rayo.direccion[eje].abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.minimo[eje] - rayo.origen[eje]) / rayo.direccion[eje];
+        if t <= 1e-6 {
+            return None;
+        }
+
+        let punto = rayo.punto_en(t);
+        for otro in [(eje + 1) % 3, (eje + 2) % 3] {
+            if punto[otro] < self.minimo[otro] || punto[otro] > self.maximo[otro] {
+                return None;
+            }
+        }
+
+        Some(Interseccion {
+            t,
+            punto,
+            normal: self.normal,
+            material: self.material.clone(),
+        })
+    }
+}
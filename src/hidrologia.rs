@@ -0,0 +1,284 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::mundo::*;
+
+// Vecinos en las cuatro direcciones del plano (x, z)
+const VECINOS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Umbrales de ríos y lagos; las distancias están en bloques
+#[derive(Clone, Debug)]
+pub struct ConfiguracionHidrologia {
+    pub umbral_rio: u32,           // Columnas drenadas a partir de las cuales nace un río
+    pub ancho_maximo: i32,
+    pub profundidad_rio: i32,      // Bloques de agua de un río de ancho 1
+    pub ancho_orilla: i32,         // Franja de arena alrededor del agua
+    pub desnivel_orilla: i32,      // Solo es orilla lo que queda como mucho esto sobre el agua
+}
+
+impl Default for ConfiguracionHidrologia {
+    fn default() -> Self {
+        Self {
+            umbral_rio: 40,
+            ancho_maximo: 5,
+            profundidad_rio: 1,
+            ancho_orilla: 1,
+            desnivel_orilla: 1,
+        }
+    }
+}
+
+/// Qué agua hay en una columna
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Agua {
+    Seca,
+    Mar,
+    Lago,
+    Rio,
+}
+
+/// Estado hidrológico de una columna
+#[derive(Clone, Copy, Debug)]
+pub struct CeldaHidrica {
+    pub superficie: i32,           // y del bloque sólido más alto
+    pub relleno: i32,              // Superficie con las depresiones llenas hasta desbordar
+    pub destino: Option<usize>,    // Celda a la que drena (ninguna en las salidas)
+    pub acumulacion: u32,          // Columnas que drenan a través de esta, incluida ella
+    pub agua: Agua,
+    pub nivel_agua: i32,           // y del bloque de agua más alto (si hay agua)
+    pub cauce: bool,               // Eje del río (sin contar el ensanchamiento)
+}
+
+/// Cuántos ríos y lagos se formaron
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResumenHidrologia {
+    pub rios: usize,               // Nacimientos de río
+    pub lagos: usize,
+    pub columnas_rio: usize,
+    pub columnas_lago: usize,
+    pub columnas_orilla: usize,
+}
+
+/// Red de drenaje de una región del mundo. El agua baja por la pendiente hasta el mar o
+/// el borde de la región; las depresiones se llenan y forman lagos que desaguan por su
+/// punto más bajo.
+pub struct RedHidrica {
+    pub minimo: (i32, i32),
+    pub ancho: usize,              // Columnas en x
+    pub largo: usize,              // Columnas en z
+    pub celdas: Vec<CeldaHidrica>,
+    pub config: ConfiguracionHidrologia,
+}
+
+// Altura del bloque más alto que no es agua (el mar del generador no cuenta como suelo)
+fn suelo(mundo: &Mundo, x: i32, z: i32) -> i32 {
+    (0..mundo.altura as i32)
+        .rev()
+        .find(|&y| !matches!(mundo.obtener(x, y, z), AIRE | AGUA))
+        .unwrap_or(0)
+}
+
+impl RedHidrica {
+    /// Analiza las columnas de `minimo` a `maximo` (x, z; ambos incluidos). Las columnas
+    /// con el suelo bajo `nivel_mar` son mar.
+    pub fn calcular(mundo: &Mundo, minimo: (i32, i32), maximo: (i32, i32), nivel_mar: i32,
+                    config: ConfiguracionHidrologia) -> Self {
+        let ancho = (maximo.0 - minimo.0 + 1).max(0) as usize;
+        let largo = (maximo.1 - minimo.1 + 1).max(0) as usize;
+        let mut celdas: Vec<CeldaHidrica> = (0..ancho * largo)
+            .map(|i| {
+                let superficie = suelo(mundo, minimo.0 + (i % ancho) as i32, minimo.1 + (i / ancho) as i32);
+                CeldaHidrica {
+                    superficie,
+                    relleno: superficie,
+                    destino: None,
+                    acumulacion: 1,
+                    agua: if superficie < nivel_mar { Agua::Mar } else { Agua::Seca },
+                    nivel_agua: nivel_mar,
+                    cauce: false,
+                }
+            })
+            .collect();
+        let mut red = Self { minimo, ancho, largo, celdas: Vec::new(), config };
+
+        // Inundación por prioridad (Barnes et al. 2014): desde el mar y el borde se visitan
+        // las columnas de menor a mayor; cada una drena hacia la que la alcanzó y su
+        // relleno nunca es menor que el de ella, así las depresiones quedan llenas
+        let mut visitada = vec![false; ancho * largo];
+        let mut cola = BinaryHeap::new();
+        for (i, celda) in celdas.iter().enumerate() {
+            let (x, z) = (i % ancho, i / ancho);
+            if celda.agua == Agua::Mar || x == 0 || z == 0 || x + 1 == ancho || z + 1 == largo {
+                visitada[i] = true;
+                cola.push(Reverse((celda.relleno, i)));
+            }
+        }
+        let mut orden = Vec::with_capacity(ancho * largo);
+        while let Some(Reverse((relleno, i))) = cola.pop() {
+            orden.push(i);
+            for vecino in red.vecinos(i) {
+                if !visitada[vecino] {
+                    visitada[vecino] = true;
+                    let celda = &mut celdas[vecino];
+                    celda.relleno = celda.superficie.max(relleno);
+                    celda.destino = Some(i);
+                    cola.push(Reverse((celda.relleno, vecino)));
+                }
+            }
+        }
+
+        // Acumulación: de arriba abajo, cada columna pasa su caudal a su destino
+        for &i in orden.iter().rev() {
+            if let Some(destino) = celdas[i].destino {
+                celdas[destino].acumulacion += celdas[i].acumulacion;
+            }
+        }
+
+        // Lagos donde el relleno supera al suelo y cauces donde el caudal basta
+        for celda in celdas.iter_mut().filter(|c| c.agua == Agua::Seca) {
+            if celda.relleno > celda.superficie {
+                celda.agua = Agua::Lago;
+                celda.nivel_agua = celda.relleno;
+            } else if celda.acumulacion >= red.config.umbral_rio {
+                celda.cauce = true;
+            }
+        }
+
+        // El río se ensancha con la raíz del caudal; el agua queda un bloque bajo la
+        // orilla y nunca por encima de la de otro tramo que la cubra
+        let cauces: Vec<usize> = (0..celdas.len()).filter(|&i| celdas[i].cauce).collect();
+        for i in cauces {
+            let centro = celdas[i];
+            let ancho_rio = ((centro.acumulacion as f64 / red.config.umbral_rio as f64).sqrt() as i32)
+                .clamp(1, red.config.ancho_maximo);
+            let radio = ancho_rio as f64 / 2.0;
+            let alcance = ancho_rio / 2;
+            let nivel = centro.superficie - 1;
+            let (cx, cz) = ((i % ancho) as i32, (i / ancho) as i32);
+            for dz in -alcance..=alcance {
+                for dx in -alcance..=alcance {
+                    let (x, z) = (cx + dx, cz + dz);
+                    if x < 0 || z < 0 || x >= ancho as i32 || z >= largo as i32
+                        || (dx * dx + dz * dz) as f64 > radio * radio
+                    {
+                        continue;
+                    }
+                    let celda = &mut celdas[z as usize * ancho + x as usize];
+                    match celda.agua {
+                        Agua::Seca => {
+                            celda.agua = Agua::Rio;
+                            celda.nivel_agua = nivel.min(celda.superficie - 1);
+                        }
+                        Agua::Rio => celda.nivel_agua = celda.nivel_agua.min(nivel),
+                        Agua::Mar | Agua::Lago => {}
+                    }
+                }
+            }
+        }
+
+        red.celdas = celdas;
+        red
+    }
+
+    // Índices de las celdas vecinas dentro de la región
+    fn vecinos(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let (x, z) = ((i % self.ancho) as i32, (i / self.ancho) as i32);
+        VECINOS.iter().filter_map(move |&(dx, dz)| {
+            let (vx, vz) = (x + dx, z + dz);
+            (vx >= 0 && vz >= 0 && (vx as usize) < self.ancho && (vz as usize) < self.largo)
+                .then(|| vz as usize * self.ancho + vx as usize)
+        })
+    }
+
+    /// Posición (x, z) en el mundo de una celda
+    pub fn posicion(&self, i: usize) -> (i32, i32) {
+        (self.minimo.0 + (i % self.ancho) as i32, self.minimo.1 + (i / self.ancho) as i32)
+    }
+
+    pub fn celda(&self, x: i32, z: i32) -> Option<&CeldaHidrica> {
+        let (lx, lz) = (x - self.minimo.0, z - self.minimo.1);
+        if lx < 0 || lz < 0 || lx as usize >= self.ancho || lz as usize >= self.largo {
+            return None;
+        }
+        self.celdas.get(lz as usize * self.ancho + lx as usize)
+    }
+
+    /// Excava los cauces, llena los lagos y pone arena en las orillas
+    pub fn aplicar(&self, mundo: &mut Mundo) -> ResumenHidrologia {
+        let mut resumen = ResumenHidrologia::default();
+
+        for (i, celda) in self.celdas.iter().enumerate() {
+            let (x, z) = self.posicion(i);
+            match celda.agua {
+                Agua::Rio => {
+                    // Los tramos anchos son más hondos
+                    let profundidad = self.config.profundidad_rio + (celda.acumulacion >= self.config.umbral_rio * 9) as i32;
+                    let lecho = celda.nivel_agua - profundidad;
+                    mundo.rellenar((x, celda.nivel_agua + 1, z), (x, celda.superficie, z), AIRE);
+                    mundo.rellenar((x, lecho + 1, z), (x, celda.nivel_agua, z), AGUA);
+                    mundo.colocar(x, lecho, z, ARENA);
+                    resumen.columnas_rio += 1;
+                }
+                Agua::Lago => {
+                    mundo.colocar(x, celda.superficie, z, ARENA);
+                    mundo.rellenar((x, celda.superficie + 1, z), (x, celda.nivel_agua, z), AGUA);
+                    resumen.columnas_lago += 1;
+                }
+                Agua::Seca | Agua::Mar => {}
+            }
+        }
+
+        // Orillas: columnas secas cerca del agua y a poca altura sobre ella
+        let alcance = self.config.ancho_orilla;
+        for (i, celda) in self.celdas.iter().enumerate().filter(|(_, c)| c.agua == Agua::Seca) {
+            let (x, z) = self.posicion(i);
+            let agua_cercana = (-alcance..=alcance)
+                .flat_map(|dx| (-alcance..=alcance).map(move |dz| (dx, dz)))
+                .filter_map(|(dx, dz)| self.celda(x + dx, z + dz))
+                .filter(|vecina| vecina.agua != Agua::Seca)
+                .map(|vecina| vecina.nivel_agua)
+                .max();
+            if let Some(nivel) = agua_cercana {
+                if celda.superficie <= nivel + self.config.desnivel_orilla
+                    && matches!(mundo.obtener(x, celda.superficie, z), PASTO | TIERRA | GRAVA | NIEVE)
+                {
+                    mundo.colocar(x, celda.superficie, z, ARENA);
+                    resumen.columnas_orilla += 1;
+                }
+            }
+        }
+
+        // Un nacimiento es un tramo de cauce al que no llega ningún otro
+        let mut afluente = vec![false; self.celdas.len()];
+        for celda in self.celdas.iter().filter(|c| c.cauce) {
+            if let Some(destino) = celda.destino {
+                afluente[destino] = true;
+            }
+        }
+        resumen.rios = (0..self.celdas.len()).filter(|&i| self.celdas[i].cauce && !afluente[i]).count();
+        resumen.lagos = self.contar_lagos();
+        resumen
+    }
+
+    // Grupos de columnas de lago conectadas
+    fn contar_lagos(&self) -> usize {
+        let mut visitada = vec![false; self.celdas.len()];
+        let mut lagos = 0;
+        for inicio in 0..self.celdas.len() {
+            if visitada[inicio] || self.celdas[inicio].agua != Agua::Lago {
+                continue;
+            }
+            lagos += 1;
+            let mut pila = vec![inicio];
+            visitada[inicio] = true;
+            while let Some(i) = pila.pop() {
+                for vecino in self.vecinos(i) {
+                    if !visitada[vecino] && self.celdas[vecino].agua == Agua::Lago {
+                        visitada[vecino] = true;
+                        pila.push(vecino);
+                    }
+                }
+            }
+        }
+        lagos
+    }
+}
@@ -0,0 +1,669 @@
+use nalgebra::{Vector3, Point3};
+use crate::cielo::CieloPreetham;
+use crate::entorno::MapaEntorno;
+use rand::Rng;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub enum TipoLuz {
+    /// Luz puntual ideal en una posición
+    Puntual { posicion: Point3<f64> },
+    /// Bloque emisivo: la luz sale de toda la superficie de la caja
+    Area { minimo: Point3<f64>, maximo: Point3<f64> },
+    /// Sol lejano; `direccion` indica hacia dónde viaja la luz y el radio angular suaviza la sombra
+    Direccional { direccion: Vector3<f64>, radio_angular: f64 },
+    /// Farol con cono de luz; los ángulos están en radianes y se miden desde el eje
+    Foco { posicion: Point3<f64>, direccion: Vector3<f64>, angulo_interior: f64, angulo_exterior: f64 },
+    /// Luz de hemisferio: `color` arriba, `color_suelo` abajo
+    Cielo { color_suelo: Vector3<f64> },
+    /// Cielo de Preetham; bajo el horizonte refleja el horizonte con el albedo `color_suelo`
+    CieloFisico { modelo: CieloPreetham, color_suelo: Vector3<f64> },
+    /// Mapa de entorno HDR; se muestrea en proporción a su luminancia
+    Entorno { mapa: Arc<MapaEntorno> },
+}
+
+#[derive(Clone)]
+pub struct Luz {
+    pub tipo: TipoLuz,
+    pub color: Vector3<f64>,
+    pub intensidad: f64,
+}
+
+/// Resultado de muestrear una luz desde un punto de la escena
+pub struct MuestraLuz {
+    pub direccion: Vector3<f64>,   // Dirección normalizada hacia la luz
+    pub distancia: f64,            // Distancia hasta el punto muestreado
+    pub radiancia: Vector3<f64>,   // Luz que llega (ya atenuada si aplica)
+    pub pdf: f64,                  // Densidad en ángulo sólido (1.0 para luces delta)
+}
+
+impl Luz {
+    pub fn puntual(posicion: Point3<f64>, color: Vector3<f64>, intensidad: f64) -> Self {
+        Self {
+            tipo: TipoLuz::Puntual { posicion },
+            color,
+            intensidad,
+        }
+    }
+
+    pub fn area(minimo: Point3<f64>, maximo: Point3<f64>, color: Vector3<f64>, intensidad: f64) -> Self {
+        Self {
+            tipo: TipoLuz::Area { minimo, maximo },
+            color,
+            intensidad,
+        }
+    }
+
+    /// Sol con tamaño angular en grados (el sol real mide ~0.53°)
+    pub fn direccional(direccion: Vector3<f64>, color: Vector3<f64>, intensidad: f64,
+                       tamano_angular: f64) -> Self {
+        Self {
+            tipo: TipoLuz::Direccional {
+                direccion: normalizar_direccion(direccion),
+                radio_angular: (tamano_angular / 2.0).to_radians(),
+            },
+            color,
+            intensidad,
+        }
+    }
+
+    /// Foco con ángulos de apertura en grados; entre interior y exterior la luz se desvanece
+    pub fn foco(posicion: Point3<f64>, direccion: Vector3<f64>, color: Vector3<f64>, intensidad: f64,
+                angulo_interior: f64, angulo_exterior: f64) -> Self {
+        Self {
+            tipo: TipoLuz::Foco {
+                posicion,
+                direccion: normalizar_direccion(direccion),
+                angulo_interior: angulo_interior.to_radians(),
+                angulo_exterior: angulo_exterior.max(angulo_interior).to_radians(),
+            },
+            color,
+            intensidad,
+        }
+    }
+
+    pub fn cielo(color: Vector3<f64>, color_suelo: Vector3<f64>, intensidad: f64) -> Self {
+        Self {
+            tipo: TipoLuz::Cielo { color_suelo },
+            color,
+            intensidad,
+        }
+    }
+
+    /// Cielo analítico según la posición del sol (`direccion_sol` es hacia dónde viaja la luz)
+    pub fn cielo_fisico(direccion_sol: Vector3<f64>, turbidez: f64, color_suelo: Vector3<f64>,
+                        intensidad: f64) -> Self {
+        Self {
+            tipo: TipoLuz::CieloFisico { modelo: CieloPreetham::nuevo(direccion_sol, turbidez), color_suelo },
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensidad,
+        }
+    }
+
+    /// Mapa de entorno; `intensidad` escala su radiancia
+    pub fn entorno(mapa: MapaEntorno, intensidad: f64) -> Self {
+        Self {
+            tipo: TipoLuz::Entorno { mapa: Arc::new(mapa) },
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensidad,
+        }
+    }
+
+    pub fn es_cielo(&self) -> bool {
+        matches!(self.tipo, TipoLuz::Cielo { .. } | TipoLuz::CieloFisico { .. } | TipoLuz::Entorno { .. })
+    }
+
+    /// Densidad con la que `muestrear` habría elegido `direccion` en una luz de cielo.
+    /// Los cielos analíticos se muestrean con la misma distribución coseno que la BRDF.
+    pub fn pdf_cielo(&self, direccion: &Vector3<f64>, pdf_coseno: f64) -> f64 {
+        match &self.tipo {
+            TipoLuz::Cielo { .. } | TipoLuz::CieloFisico { .. } => pdf_coseno,
+            TipoLuz::Entorno { mapa } => mapa.pdf(direccion),
+            _ => 0.0,
+        }
+    }
+
+    /// Las luces delta no pueden ser alcanzadas por rayos aleatorios
+    pub fn es_delta(&self) -> bool {
+        matches!(self.tipo, TipoLuz::Puntual { .. } | TipoLuz::Direccional { .. } | TipoLuz::Foco { .. })
+    }
+
+    /// Radiancia de la luz de cielo en una dirección (cero para el resto de luces)
+    pub fn radiancia_cielo(&self, direccion: &Vector3<f64>) -> Vector3<f64> {
+        match &self.tipo {
+            TipoLuz::Cielo { color_suelo } => {
+                let t = 0.5 * (direccion.y + 1.0);
+                (color_suelo * (1.0 - t) + self.color * t) * self.intensidad
+            }
+            TipoLuz::CieloFisico { modelo, color_suelo } => {
+                let cielo = modelo.radiancia(direccion).component_mul(&self.color) * self.intensidad;
+                if direccion.y < 0.0 { cielo.component_mul(color_suelo) } else { cielo }
+            }
+            TipoLuz::Entorno { mapa } => mapa.radiancia(direccion).component_mul(&self.color) * self.intensidad,
+            _ => Vector3::zeros(),
+        }
+    }
+
+    /// Radiancia del disco solar vista en una dirección: la irradiancia del sol
+    /// repartida en su ángulo sólido. Solo la ven los rayos de cámara y especulares,
+    /// porque los rebotes difusos ya muestrean el sol directamente.
+    pub fn radiancia_disco(&self, direccion: &Vector3<f64>) -> Vector3<f64> {
+        match &self.tipo {
+            TipoLuz::Direccional { direccion: direccion_luz, radio_angular } if *radio_angular > 0.0 => {
+                if (-direccion_luz).dot(direccion) < radio_angular.cos() {
+                    return Vector3::zeros();
+                }
+                let angulo_solido = 2.0 * PI * (1.0 - radio_angular.cos());
+                self.color * (self.intensidad / angulo_solido)
+            }
+            _ => Vector3::zeros(),
+        }
+    }
+
+    /// Densidad en ángulo sólido con la que `muestrear` habría elegido `punto_luz` desde `origen`.
+    /// Solo las luces de área pueden ser golpeadas por un rayo; las demás devuelven 0.
+    pub fn pdf(&self, origen: &Point3<f64>, punto_luz: &Point3<f64>, normal_luz: &Vector3<f64>) -> f64 {
+        match &self.tipo {
+            TipoLuz::Area { minimo, maximo } => {
+                let margen = 1e-3;
+                let dentro = (0..3).all(|i| {
+                    punto_luz[i] >= minimo[i] - margen && punto_luz[i] <= maximo[i] + margen
+                });
+                if !dentro {
+                    return 0.0;
+                }
+
+                let hacia_luz = punto_luz - origen;
+                let distancia2 = hacia_luz.magnitude_squared();
+                let coseno_luz = -hacia_luz.dot(normal_luz) / distancia2.sqrt();
+                if coseno_luz <= 0.0 {
+                    return 0.0;
+                }
+                distancia2 / (coseno_luz * area_caja(minimo, maximo))
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn muestrear<R: Rng>(&self, punto: &Point3<f64>, normal: &Vector3<f64>, rng: &mut R)
+        -> Option<MuestraLuz> {
+        match &self.tipo {
+            TipoLuz::Puntual { posicion } => {
+                let hacia_luz = posicion - punto;
+                let distancia = hacia_luz.magnitude();
+                if distancia <= 0.0 {
+                    return None;
+                }
+
+                Some(MuestraLuz {
+                    direccion: hacia_luz / distancia,
+                    distancia,
+                    radiancia: self.color * self.intensidad / (distancia * distancia),
+                    pdf: 1.0,
+                })
+            }
+            TipoLuz::Area { minimo, maximo } => {
+                let (punto_luz, normal_luz) = muestrear_superficie_caja(minimo, maximo, rng);
+                let hacia_luz = punto_luz - punto;
+                let distancia = hacia_luz.magnitude();
+                if distancia <= 0.0 {
+                    return None;
+                }
+
+                let direccion = hacia_luz / distancia;
+                let coseno_luz = -direccion.dot(&normal_luz);
+                if coseno_luz <= 0.0 {
+                    // La cara muestreada no mira hacia el punto
+                    return None;
+                }
+
+                Some(MuestraLuz {
+                    direccion,
+                    distancia,
+                    radiancia: self.color * self.intensidad,
+                    pdf: distancia * distancia / (coseno_luz * area_caja(minimo, maximo)),
+                })
+            }
+            TipoLuz::Direccional { direccion, radio_angular } => {
+                // Un punto al azar del disco solar da la penumbra
+                let direccion = muestrear_cono(&-direccion, *radio_angular, rng);
+
+                Some(MuestraLuz {
+                    direccion,
+                    distancia: f64::INFINITY,
+                    radiancia: self.color * self.intensidad,
+                    pdf: 1.0,
+                })
+            }
+            TipoLuz::Foco { posicion, direccion, angulo_interior, angulo_exterior } => {
+                let hacia_luz = posicion - punto;
+                let distancia = hacia_luz.magnitude();
+                if distancia <= 0.0 {
+                    return None;
+                }
+
+                let hacia_luz = hacia_luz / distancia;
+                let atenuacion = atenuacion_cono(
+                    -hacia_luz.dot(direccion),
+                    angulo_interior.cos(),
+                    angulo_exterior.cos(),
+                );
+                if atenuacion <= 0.0 {
+                    return None;
+                }
+
+                Some(MuestraLuz {
+                    direccion: hacia_luz,
+                    distancia,
+                    radiancia: self.color * (self.intensidad * atenuacion / (distancia * distancia)),
+                    pdf: 1.0,
+                })
+            }
+            TipoLuz::Cielo { .. } | TipoLuz::CieloFisico { .. } => {
+                // Muestreo coseno alrededor de la normal
+                let direccion = muestrear_coseno(normal, rng);
+                let coseno = direccion.dot(normal);
+                if coseno <= 0.0 {
+                    return None;
+                }
+
+                Some(MuestraLuz {
+                    direccion,
+                    distancia: f64::INFINITY,
+                    radiancia: self.radiancia_cielo(&direccion),
+                    pdf: coseno / PI,
+                })
+            }
+            TipoLuz::Entorno { mapa } => {
+                let (direccion, pdf) = mapa.muestrear(rng);
+                if pdf <= 0.0 || direccion.dot(normal) <= 0.0 {
+                    return None;
+                }
+
+                Some(MuestraLuz {
+                    direccion,
+                    distancia: f64::INFINITY,
+                    radiancia: self.radiancia_cielo(&direccion),
+                    pdf,
+                })
+            }
+        }
+    }
+}
+
+// La dirección nula no indica ninguna: se avisa en depuración y se usa -Y (hacia abajo)
+fn normalizar_direccion(direccion: Vector3<f64>) -> Vector3<f64> {
+    debug_assert!(direccion.norm_squared() > 0.0, "dirección de luz nula");
+    direccion.try_normalize(0.0).unwrap_or(Vector3::new(0.0, -1.0, 0.0))
+}
+
+// Transición suave entre el cono interior y el exterior
+fn atenuacion_cono(coseno: f64, coseno_interior: f64, coseno_exterior: f64) -> f64 {
+    if coseno >= coseno_interior {
+        return 1.0;
+    }
+    if coseno <= coseno_exterior {
+        return 0.0;
+    }
+    let t = (coseno - coseno_exterior) / (coseno_interior - coseno_exterior);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Base ortonormal (u, v) perpendicular a `n`
+pub fn base_ortonormal(n: &Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let auxiliar = if n.x.abs() > 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let u = n.cross(&auxiliar).normalize();
+    let v = n.cross(&u);
+    (u, v)
+}
+
+/// Dirección del hemisferio de `normal` con densidad coseno / π
+pub fn muestrear_coseno<R: Rng>(normal: &Vector3<f64>, rng: &mut R) -> Vector3<f64> {
+    let r1: f64 = rng.gen();
+    let r2: f64 = rng.gen();
+    let phi = 2.0 * PI * r1;
+    let r = r2.sqrt();
+    let (u, v) = base_ortonormal(normal);
+    (u * (r * phi.cos()) + v * (r * phi.sin()) + normal * (1.0 - r2).sqrt()).normalize()
+}
+
+/// Dirección uniforme dentro de un cono de semiángulo `radio` alrededor de `eje`
+fn muestrear_cono<R: Rng>(eje: &Vector3<f64>, radio: f64, rng: &mut R) -> Vector3<f64> {
+    if radio <= 0.0 {
+        return *eje;
+    }
+    let coseno_max = radio.cos();
+    let coseno = 1.0 - rng.gen::<f64>() * (1.0 - coseno_max);
+    let seno = (1.0 - coseno * coseno).sqrt();
+    let phi = 2.0 * PI * rng.gen::<f64>();
+    let (u, v) = base_ortonormal(eje);
+    (u * (seno * phi.cos()) + v * (seno * phi.sin()) + eje * coseno).normalize()
+}
+
+fn area_caja(minimo: &Point3<f64>, maximo: &Point3<f64>) -> f64 {
+    let d = maximo - minimo;
+    2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+}
+
+/// Punto uniforme sobre la superficie de la caja, junto con la normal de su cara
+fn muestrear_superficie_caja<R: Rng>(minimo: &Point3<f64>, maximo: &Point3<f64>, rng: &mut R)
+    -> (Point3<f64>, Vector3<f64>) {
+    let d = maximo - minimo;
+    let areas = [d.y * d.z, d.z * d.x, d.x * d.y];
+    let total = areas[0] + areas[1] + areas[2];
+
+    // Elegir eje proporcional al área de sus dos caras
+    let mut r = rng.gen::<f64>() * total;
+    let mut eje = 2;
+    for (i, area) in areas.iter().enumerate() {
+        if r < *area {
+            eje = i;
+            break;
+        }
+        r -= area;
+    }
+
+    let mut punto = Point3::new(
+        minimo.x + rng.gen::<f64>() * d.x,
+        minimo.y + rng.gen::<f64>() * d.y,
+        minimo.z + rng.gen::<f64>() * d.z,
+    );
+    let mut normal = Vector3::zeros();
+    if rng.gen_bool(0.5) {
+        punto[eje] = maximo[eje];
+        normal[eje] = 1.0;
+    } else {
+        punto[eje] = minimo[eje];
+        normal[eje] = -1.0;
+    }
+
+    (punto, normal)
+}
+
+// ====================== CONFIGURACIÓN DE ILUMINACIÓN ======================
+
+/// Parámetros de la iluminación de día de las escenas Minecraft
+#[derive(Clone)]
+pub struct ConfiguracionIluminacion {
+    pub direccion_sol: Vector3<f64>,
+    pub color_sol: Vector3<f64>,
+    pub intensidad_sol: f64,
+    pub tamano_angular_sol: f64,          // En grados
+    pub turbidez: f64,
+    pub color_suelo: Vector3<f64>,
+    pub intensidad_cielo: f64,
+    pub faroles: Vec<Farol>,
+}
+
+/// Foco de farol: posición, hacia dónde alumbra y su luz (ángulos en grados)
+#[derive(Clone)]
+pub struct Farol {
+    pub posicion: Point3<f64>,
+    pub direccion: Vector3<f64>,
+    pub color: Vector3<f64>,
+    pub intensidad: f64,
+    pub angulo_interior: f64,
+    pub angulo_exterior: f64,
+}
+
+impl Farol {
+    /// Farol de luz cálida con el cono de los faroles del diorama
+    pub fn nuevo(posicion: Point3<f64>, direccion: Vector3<f64>) -> Self {
+        Self {
+            posicion,
+            direccion,
+            color: Vector3::new(1.0, 0.8, 0.5),
+            intensidad: 40.0,
+            angulo_interior: 20.0,
+            angulo_exterior: 35.0,
+        }
+    }
+
+    pub fn luz(&self) -> Luz {
+        Luz::foco(self.posicion, self.direccion, self.color, self.intensidad,
+                  self.angulo_interior, self.angulo_exterior)
+    }
+}
+
+impl Default for ConfiguracionIluminacion {
+    fn default() -> Self {
+        Self {
+            direccion_sol: Vector3::new(-0.5, -0.7, -0.3),
+            color_sol: Vector3::new(1.0, 1.0, 1.0),
+            intensidad_sol: 4.0,
+            tamano_angular_sol: 2.0,
+            turbidez: 3.0,
+            color_suelo: Vector3::new(0.35, 0.3, 0.25),
+            intensidad_cielo: 1.0,
+            faroles: Vec::new(),
+        }
+    }
+}
+
+// Iluminación de día usada por las escenas Minecraft
+pub fn crear_iluminacion_minecraft() -> Vec<Luz> {
+    crear_iluminacion_minecraft_con(&ConfiguracionIluminacion::default())
+}
+
+pub fn crear_iluminacion_minecraft_con(config: &ConfiguracionIluminacion) -> Vec<Luz> {
+    // El color del sol sale de la misma atmósfera que el cielo
+    let cielo = Luz::cielo_fisico(config.direccion_sol, config.turbidez, config.color_suelo,
+                                  config.intensidad_cielo);
+    let color_sol = match &cielo.tipo {
+        TipoLuz::CieloFisico { modelo, .. } => config.color_sol.component_mul(&modelo.transmitancia_sol()),
+        _ => config.color_sol,
+    };
+
+    let mut luces = vec![
+        Luz::direccional(config.direccion_sol, color_sol, config.intensidad_sol,
+                         config.tamano_angular_sol),
+        cielo,
+    ];
+
+    luces.extend(config.faroles.iter().map(Farol::luz));
+
+    luces
+}
+
+// ====================== ARCHIVOS DE ESCENA ======================
+
+/// Lee las luces de un archivo de escena de texto.
+///
+/// Cada línea es un tipo de luz seguido de pares `clave valores`; `#` inicia un comentario:
+///
+/// ```text
+/// sol      direccion -0.5 -0.7 -0.3 color 1 0.95 0.85 intensidad 3 tamano_angular 2
+/// cielo    color 0.53 0.81 0.92 suelo 0.35 0.3 0.25 intensidad 0.6
+/// preetham direccion -0.5 -0.7 -0.3 turbidez 3 suelo 0.35 0.3 0.25 intensidad 1
+/// entorno  archivo estudio.hdr rotacion 90 intensidad 1.5
+/// foco     posicion 0 7 -4.5 direccion 0 -1 0 color 1 0.8 0.5 intensidad 40 interior 20 exterior 35
+/// puntual  posicion 50 80 50 color 1 1 1 intensidad 200
+/// area     minimo 0 0 0 maximo 1 1 1 color 4 1.4 0.2 intensidad 1
+/// ```
+pub fn cargar_luces(ruta: &str) -> Result<Vec<Luz>, String> {
+    let texto = fs::read_to_string(ruta)
+        .map_err(|e| format!("No se pudo leer {}: {}", ruta, e))?;
+    interpretar_luces(&texto)
+}
+
+pub fn interpretar_luces(texto: &str) -> Result<Vec<Luz>, String> {
+    let mut luces = Vec::new();
+
+    for (numero, linea) in texto.lines().enumerate() {
+        let linea = linea.split('#').next().unwrap_or("").trim();
+        if linea.is_empty() {
+            continue;
+        }
+
+        let luz = interpretar_linea(linea).map_err(|e| format!("línea {}: {}", numero + 1, e))?;
+        luces.push(luz);
+    }
+
+    Ok(luces)
+}
+
+fn interpretar_linea(linea: &str) -> Result<Luz, String> {
+    let mut partes = linea.split_whitespace();
+    let tipo = partes.next().unwrap_or("");
+    let claves: &[&str] = match tipo {
+        "puntual" => &["posicion", "color", "intensidad"],
+        "area" => &["minimo", "maximo", "color", "intensidad"],
+        "sol" | "direccional" => &["direccion", "color", "intensidad", "tamano_angular"],
+        "foco" => &["posicion", "direccion", "color", "intensidad", "interior", "exterior"],
+        "cielo" => &["color", "suelo", "intensidad"],
+        "preetham" | "cielo_fisico" => &["direccion", "turbidez", "suelo", "intensidad"],
+        "entorno" => &["archivo", "rotacion", "intensidad"],
+        _ => return Err(format!("tipo de luz desconocido '{}'", tipo)),
+    };
+
+    // Agrupar los números que siguen a cada clave; `archivo` lleva una ruta
+    let mut valores: HashMap<&str, Vec<f64>> = HashMap::new();
+    let mut archivo: Option<&str> = None;
+    let mut clave_actual: Option<&str> = None;
+    for parte in partes {
+        if clave_actual == Some("archivo") && archivo.is_none() {
+            archivo = Some(parte);
+            continue;
+        }
+        match parte.parse::<f64>() {
+            Ok(numero) => match clave_actual {
+                Some(clave) => valores.entry(clave).or_default().push(numero),
+                None => return Err(format!("valor '{}' sin clave", parte)),
+            },
+            Err(_) if !claves.contains(&parte) => {
+                return Err(format!("clave desconocida '{}' para '{}'", parte, tipo));
+            }
+            Err(_) => {
+                clave_actual = Some(parte);
+                valores.entry(parte).or_default();
+            }
+        }
+    }
+
+    let escalar = |clave: &str, defecto: f64| -> Result<f64, String> {
+        match valores.get(clave) {
+            None => Ok(defecto),
+            Some(v) if v.len() == 1 => Ok(v[0]),
+            Some(_) => Err(format!("'{}' espera un valor", clave)),
+        }
+    };
+    let vector = |clave: &str, defecto: Option<Vector3<f64>>| -> Result<Vector3<f64>, String> {
+        match (valores.get(clave), defecto) {
+            (Some(v), _) if v.len() == 3 => Ok(Vector3::new(v[0], v[1], v[2])),
+            (Some(_), _) => Err(format!("'{}' espera tres valores", clave)),
+            (None, Some(defecto)) => Ok(defecto),
+            (None, None) => Err(format!("falta '{}'", clave)),
+        }
+    };
+    // Las direcciones se normalizan: el vector nulo no indica ninguna
+    let direccion = |clave: &str, defecto: Option<Vector3<f64>>| -> Result<Vector3<f64>, String> {
+        let v = vector(clave, defecto)?;
+        if v.norm_squared() == 0.0 {
+            return Err(format!("'{}' no puede ser nula", clave));
+        }
+        Ok(v)
+    };
+
+    let blanco = Some(Vector3::new(1.0, 1.0, 1.0));
+    let color = vector("color", blanco)?;
+    let intensidad = escalar("intensidad", 1.0)?;
+
+    match tipo {
+        "puntual" => Ok(Luz::puntual(Point3::from(vector("posicion", None)?), color, intensidad)),
+        "area" => Ok(Luz::area(
+            Point3::from(vector("minimo", None)?),
+            Point3::from(vector("maximo", None)?),
+            color,
+            intensidad,
+        )),
+        "sol" | "direccional" => Ok(Luz::direccional(
+            direccion("direccion", None)?,
+            color,
+            intensidad,
+            escalar("tamano_angular", 0.53)?,
+        )),
+        "foco" => Ok(Luz::foco(
+            Point3::from(vector("posicion", None)?),
+            direccion("direccion", Some(Vector3::new(0.0, -1.0, 0.0)))?,
+            color,
+            intensidad,
+            escalar("interior", 20.0)?,
+            escalar("exterior", 30.0)?,
+        )),
+        "cielo" => Ok(Luz::cielo(color, vector("suelo", Some(Vector3::new(0.3, 0.3, 0.3)))?, intensidad)),
+        "preetham" | "cielo_fisico" => Ok(Luz::cielo_fisico(
+            direccion("direccion", None)?,
+            escalar("turbidez", 3.0)?,
+            vector("suelo", Some(Vector3::new(0.3, 0.3, 0.3)))?,
+            intensidad,
+        )),
+        "entorno" => {
+            let ruta = archivo.ok_or_else(|| "falta 'archivo'".to_string())?;
+            let mapa = MapaEntorno::cargar(ruta)?.con_rotacion(escalar("rotacion", 0.0)?);
+            Ok(Luz::entorno(mapa, intensidad))
+        }
+        _ => unreachable!("tipo comprobado al leer las claves"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lee_sol_foco_y_cielo() {
+        let luces = interpretar_luces(
+            "sol direccion 0 -2 0 color 1 0.9 0.8 intensidad 3 tamano_angular 2\n\
+             # comentario\n\
+             foco posicion 0 7 -4.5 direccion 0 -1 0 intensidad 40 interior 20 exterior 35\n\
+             cielo color 0.5 0.8 0.9 suelo 0.3 0.3 0.3 intensidad 0.6",
+        )
+        .unwrap();
+        assert_eq!(luces.len(), 3);
+
+        match luces[0].tipo {
+            TipoLuz::Direccional { direccion, radio_angular } => {
+                assert_eq!(direccion, Vector3::new(0.0, -1.0, 0.0));
+                assert!((radio_angular - 1f64.to_radians()).abs() < 1e-12);
+            }
+            _ => panic!("se esperaba un sol"),
+        }
+        assert_eq!(luces[0].color, Vector3::new(1.0, 0.9, 0.8));
+        assert_eq!(luces[0].intensidad, 3.0);
+
+        match luces[1].tipo {
+            TipoLuz::Foco { posicion, angulo_interior, angulo_exterior, .. } => {
+                assert_eq!(posicion, Point3::new(0.0, 7.0, -4.5));
+                assert!((angulo_interior - 20f64.to_radians()).abs() < 1e-12);
+                assert!((angulo_exterior - 35f64.to_radians()).abs() < 1e-12);
+            }
+            _ => panic!("se esperaba un foco"),
+        }
+        assert_eq!(luces[1].color, Vector3::new(1.0, 1.0, 1.0));
+
+        match luces[2].tipo {
+            TipoLuz::Cielo { color_suelo } => assert_eq!(color_suelo, Vector3::new(0.3, 0.3, 0.3)),
+            _ => panic!("se esperaba un cielo"),
+        }
+        assert_eq!(luces[2].intensidad, 0.6);
+    }
+
+    #[test]
+    fn rechaza_clave_desconocida() {
+        let error = interpretar_luces("sol direccion 0 -1 0\nfoco posicion 0 1 0 brillo 3")
+            .err()
+            .unwrap();
+        assert!(error.contains("línea 2"), "{}", error);
+        assert!(error.contains("'brillo'"), "{}", error);
+    }
+
+    #[test]
+    fn rechaza_direccion_nula() {
+        let error = interpretar_luces("sol direccion 0 0 0").err().unwrap();
+        assert!(error.contains("línea 1"), "{}", error);
+        assert!(error.contains("'direccion'"), "{}", error);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, VecDeque};
+use nalgebra::Vector3;
+use crate::mundo::*;
+
+/// Nivel de luz del cielo abierto y de los bloques que emiten
+pub const LUZ_MAXIMA: u8 = 15;
+
+/// Cómo se convierten los niveles de luz en el color de los vértices
+#[derive(Clone, Debug)]
+pub struct ConfiguracionLuz {
+    pub color_cielo: Vector3<f64>,     // Luz del cielo con nivel 15 (más oscura de noche)
+    pub color_bloques: Vector3<f64>,   // Luz de la lava y la piedra luminosa con nivel 15
+    pub caida: f64,                    // Factor de brillo por cada nivel por debajo de 15
+    pub oclusion: [f64; 4],            // Brillo de una esquina con 0 a 3 bloques alrededor
+    pub ambiente: f64,                 // Brillo mínimo, para que nada quede negro del todo
+}
+
+impl Default for ConfiguracionLuz {
+    fn default() -> Self {
+        Self {
+            color_cielo: Vector3::new(1.0, 0.97, 0.92),
+            color_bloques: Vector3::new(1.0, 0.78, 0.5),
+            caida: 0.8,
+            oclusion: [1.0, 0.8, 0.65, 0.5],
+            ambiente: 0.04,
+        }
+    }
+}
+
+impl ConfiguracionLuz {
+    /// Cielo de noche: solo la luz de los bloques ilumina de verdad
+    pub fn noche() -> Self {
+        Self {
+            color_cielo: Vector3::new(0.08, 0.1, 0.2),
+            ..Self::default()
+        }
+    }
+}
+
+/// Luz de una esquina de una cara
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LuzVertice {
+    pub cielo: u8,                 // Nivel suavizado en cuartos (0 a 60)
+    pub bloques: u8,               // Nivel suavizado en cuartos (0 a 60)
+    pub oclusion: u8,              // Bloques que tapan la esquina (0 a 3)
+}
+
+impl LuzVertice {
+    /// Brillo de la esquina (multiplica al color del bloque)
+    pub fn brillo(&self, config: &ConfiguracionLuz) -> Vector3<f64> {
+        let curva = |cuartos: u8| match cuartos {
+            0 => 0.0,
+            _ => config.caida.powf(LUZ_MAXIMA as f64 - cuartos as f64 / 4.0),
+        };
+        let luz = (config.color_cielo * curva(self.cielo)).sup(&(config.color_bloques * curva(self.bloques)));
+        luz.map(|c| (c * config.oclusion[self.oclusion as usize]).max(config.ambiente).min(1.0))
+    }
+}
+
+/// Cuánta luz pierde al atravesar el bloque, además del nivel que pierde por
+/// distancia (`None` si es opaco)
+fn atenuacion(mundo: &Mundo, id: IdBloque) -> Option<u8> {
+    if id == AIRE {
+        return Some(0);
+    }
+    match mundo.paleta.material(id) {
+        Some(material) if material.transparencia > 0.0 || material.recorte_alfa.is_some() => Some(1),
+        Some(_) => None,
+        None => Some(0),
+    }
+}
+
+/// Nivel de luz que emite el bloque: el máximo si su material es emisivo
+pub fn luz_emitida(mundo: &Mundo, id: IdBloque) -> u8 {
+    match mundo.paleta.material(id) {
+        Some(material) if material.es_emisivo() => LUZ_MAXIMA,
+        _ => 0,
+    }
+}
+
+/// Niveles de luz (0 a 15) de cada posición del mundo, como en Minecraft: la del cielo
+/// baja entera por las columnas abiertas y la de los bloques sale de los emisores, y
+/// ambas se reparten perdiendo un nivel por bloque a través del aire, el agua y las hojas.
+pub struct MapaLuz {
+    altura: usize,
+    chunks: BTreeMap<(i32, i32), Vec<u8>>,     // Cielo en los 4 bits altos, bloques en los bajos
+}
+
+impl MapaLuz {
+    pub fn calcular(mundo: &Mundo) -> Self {
+        let tamano = (TAMANO_CHUNK * TAMANO_CHUNK) as usize * mundo.altura;
+        let mut mapa = Self {
+            altura: mundo.altura,
+            chunks: mundo.chunks().map(|(clave, _)| (clave, vec![0; tamano])).collect(),
+        };
+
+        // Cielo: cada columna baja desde arriba hasta el primer bloque opaco
+        let mut cielo = VecDeque::new();
+        let claves: Vec<(i32, i32)> = mapa.chunks.keys().copied().collect();
+        for (cx, cz) in claves.iter().copied() {
+            for lx in 0..TAMANO_CHUNK {
+                for lz in 0..TAMANO_CHUNK {
+                    let (x, z) = (cx * TAMANO_CHUNK + lx, cz * TAMANO_CHUNK + lz);
+                    let mut nivel = LUZ_MAXIMA;
+                    for y in (0..mundo.altura as i32).rev() {
+                        nivel = match atenuacion(mundo, mundo.obtener(x, y, z)) {
+                            Some(perdida) => nivel.saturating_sub(perdida),
+                            None => 0,
+                        };
+                        if nivel == 0 {
+                            break;
+                        }
+                        mapa.poner(x, y, z, true, nivel);
+                        cielo.push_back((x, y, z));
+                    }
+                }
+            }
+        }
+        mapa.propagar(mundo, cielo, true);
+
+        // Bloques: desde cada emisor
+        let emisores: VecDeque<Posicion> = mundo
+            .bloques()
+            .filter(|&(_, id)| luz_emitida(mundo, id) > 0)
+            .map(|(posicion, id)| {
+                let (x, y, z) = posicion;
+                mapa.poner(x, y, z, false, luz_emitida(mundo, id));
+                posicion
+            })
+            .collect();
+        mapa.propagar(mundo, emisores, false);
+        mapa
+    }
+
+    // Posición dentro del vector de su chunk
+    fn ubicar(&self, x: i32, y: i32, z: i32) -> Option<((i32, i32), usize)> {
+        if y < 0 || y as usize >= self.altura {
+            return None;
+        }
+        let clave = Mundo::chunk_de(x, z);
+        let (lx, lz) = (x.rem_euclid(TAMANO_CHUNK), z.rem_euclid(TAMANO_CHUNK));
+        Some((clave, ((y * TAMANO_CHUNK + lz) * TAMANO_CHUNK + lx) as usize))
+    }
+
+    fn poner(&mut self, x: i32, y: i32, z: i32, cielo: bool, nivel: u8) {
+        if let Some((clave, i)) = self.ubicar(x, y, z) {
+            if let Some(valores) = self.chunks.get_mut(&clave) {
+                valores[i] = if cielo { (valores[i] & 0x0F) | (nivel << 4) } else { (valores[i] & 0xF0) | nivel };
+            }
+        }
+    }
+
+    // Reparte la luz desde las posiciones de la cola hacia sus seis vecinos
+    fn propagar(&mut self, mundo: &Mundo, mut cola: VecDeque<Posicion>, cielo: bool) {
+        let nivel_de = |mapa: &Self, x, y, z| if cielo { mapa.cielo(x, y, z) } else { mapa.bloques(x, y, z) };
+        while let Some((x, y, z)) = cola.pop_front() {
+            let nivel = nivel_de(self, x, y, z);
+            for (dx, dy, dz) in [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)] {
+                let (vx, vy, vz) = (x + dx, y + dy, z + dz);
+                let dentro = self.ubicar(vx, vy, vz).is_some_and(|(clave, _)| self.chunks.contains_key(&clave));
+                let perdida = match atenuacion(mundo, mundo.obtener(vx, vy, vz)) {
+                    Some(perdida) if dentro => perdida + 1,
+                    _ => continue,
+                };
+                let nuevo = nivel.saturating_sub(perdida);
+                if nuevo > nivel_de(self, vx, vy, vz) {
+                    self.poner(vx, vy, vz, cielo, nuevo);
+                    cola.push_back((vx, vy, vz));
+                }
+            }
+        }
+    }
+
+    /// Luz del cielo; fuera de los chunks calculados es cielo abierto
+    pub fn cielo(&self, x: i32, y: i32, z: i32) -> u8 {
+        match self.ubicar(x, y, z) {
+            Some((clave, i)) => self.chunks.get(&clave).map_or(LUZ_MAXIMA, |valores| valores[i] >> 4),
+            None if y >= 0 => LUZ_MAXIMA,
+            None => 0,
+        }
+    }
+
+    /// Luz de los bloques emisores
+    pub fn bloques(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.ubicar(x, y, z)
+            .and_then(|(clave, i)| self.chunks.get(&clave).map(|valores| valores[i] & 0x0F))
+            .unwrap_or(0)
+    }
+
+    /// Chunks cuya luz cambió respecto a otro mapa del mismo mundo
+    pub fn chunks_distintos(&self, otro: &MapaLuz) -> Vec<(i32, i32)> {
+        let mut claves: Vec<(i32, i32)> = self
+            .chunks
+            .iter()
+            .filter(|&(clave, valores)| otro.chunks.get(clave) != Some(valores))
+            .map(|(clave, _)| *clave)
+            .collect();
+        claves.extend(otro.chunks.keys().filter(|clave| !self.chunks.contains_key(clave)));
+        claves
+    }
+
+    /// Luz de las cuatro esquinas de la cara del bloque en `posicion`, en el orden de
+    /// `Cara::esquinas`. Cada esquina promedia las cuatro posiciones delante de ella que
+    /// dejan pasar la luz (iluminación suave) y cuenta los bloques que la tapan
+    /// (oclusión ambiental).
+    pub fn luz_cara(&self, mundo: &Mundo, posicion: [i32; 3], eje: usize, positiva: bool) -> [LuzVertice; 4] {
+        let (u, v) = ((eje + 1) % 3, (eje + 2) % 3);
+        let mut delante = posicion;
+        delante[eje] += if positiva { 1 } else { -1 };
+
+        // Esquinas en el orden de `Cara::esquinas`, como signos en (u, v)
+        let signos = if positiva {
+            [(-1, -1), (1, -1), (1, 1), (-1, 1)]
+        } else {
+            [(-1, -1), (-1, 1), (1, 1), (1, -1)]
+        };
+        signos.map(|(su, sv)| {
+            let desplazada = |du: i32, dv: i32| {
+                let mut p = delante;
+                p[u] += du;
+                p[v] += dv;
+                p
+            };
+            let opaca = |p: [i32; 3]| atenuacion(mundo, mundo.obtener(p[0], p[1], p[2])).is_none();
+            let (lado_u, lado_v, rincon) = (desplazada(su, 0), desplazada(0, sv), desplazada(su, sv));
+            let (tapa_u, tapa_v) = (opaca(lado_u), opaca(lado_v));
+            // Con los dos lados tapados el rincón no se ve
+            let tapa_rincon = (tapa_u && tapa_v) || opaca(rincon);
+            let oclusion = if tapa_u && tapa_v { 3 } else { tapa_u as u8 + tapa_v as u8 + tapa_rincon as u8 };
+
+            let visibles: Vec<[i32; 3]> = [(delante, false), (lado_u, tapa_u), (lado_v, tapa_v), (rincon, tapa_rincon)]
+                .iter()
+                .filter(|&&(_, tapada)| !tapada)
+                .map(|&(p, _)| p)
+                .collect();
+            let promedio = |nivel: &dyn Fn([i32; 3]) -> u8| {
+                let suma: u32 = visibles.iter().map(|&p| nivel(p) as u32).sum();
+                (suma * 4 / visibles.len() as u32) as u8
+            };
+            LuzVertice {
+                cielo: promedio(&|p| self.cielo(p[0], p[1], p[2])),
+                bloques: promedio(&|p| self.bloques(p[0], p[1], p[2])),
+                oclusion,
+            }
+        })
+    }
+}
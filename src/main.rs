@@ -1,30 +1,87 @@
 ﻿use raylib::prelude::*;
-use rand::Rng;
+use nalgebra::Point3;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
 
+mod animacion;
+mod arboles;
+mod camara;
+mod cielo;
+mod distribucion;
+mod entorno;
+mod erosion;
+mod escena;
+mod geometria;
+mod hidrologia;
+mod iluminacion;
+mod luz;
+mod mallado;
+mod materiales;
+mod muestreo;
+mod mundo;
+mod pasadas;
+mod reduccion_ruido;
+mod renderizador;
+mod ruido;
+mod simulacion;
+mod subsuelo;
+mod terreno;
+mod tonos;
+
+use animacion::{ConfiguracionSecuencia, TrayectoriaCamara};
+use camara::{Camara, Proyeccion};
+use luz::{ConfiguracionLuz, LuzVertice, MapaLuz, LUZ_MAXIMA};
+use mallado::{Cara, MallaVoxel};
+use cielo::CieloPreetham;
+use muestreo::{FiltroReconstruccion, Muestreador, PatronMuestreo};
+use iluminacion::Luz;
+use mundo::{Mundo, Posicion, AGUA, GRAVA};
+use pasadas::PasadasAuxiliares;
+use reduccion_ruido::ConfiguracionRuido;
+use ruido::aleatorio_celda;
+use renderizador::{ConfiguracionAdaptativa, ModoRender, RenderProgresivo};
+use simulacion::Simulacion;
+use tonos::{OperadorTonos, Revelado};
+
+// Resolución de la vista previa del ray tracer en CPU (1/5 de la ventana)
+const ANCHO_VISTA_CPU: usize = 280;
+const ALTO_VISTA_CPU: usize = 180;
+
+// Secuencias giratorias: paso fijo de tiempo y duración de una vuelta
+const CUADROS_POR_SEGUNDO: f64 = 30.0;
+const DURACION_GIRO: f64 = 8.0;
+const DIRECTORIO_CUADROS: &str = "cuadros";
+
+// Distancia inicial de la cámara al centro del diorama
+const ZOOM_INICIAL: f32 = 50.0;
+
+// Ticks por segundo de la simulación del agua y la arena
+const TICKS_POR_SEGUNDO: f32 = 4.0;
+
+// Árbol plantado como bloques en el terreno. Para las sombras se aproxima con el
+// tronco y una esfera en la copa.
 struct Tree {
     x: f32,
     z: f32,
+    base_y: f32,
     height: f32,
-    leaf_layers: i32,
-    tree_type: TreeType,
-}
-
-#[derive(Clone, Copy)]
-enum TreeType {
-    Oak,
-    Cherry,
-    Birch,
+    crown_radius: f32,
 }
 
+// Rocas y flores con la altura (y del centro o de la base) tomada de la superficie
 struct Rock {
     x: f32,
     z: f32,
+    y: f32,
     size: f32,
 }
 
 struct Flower {
     x: f32,
     z: f32,
+    y: f32,
     color: Color,
 }
 
@@ -44,16 +101,14 @@ fn is_in_shadow(x: f32, y: f32, z: f32, trees: &Vec<Tree>, rocks: &Vec<Rock>, li
             let dz = test_pos.z - tree.z;
             let dist = (dx * dx + dz * dz).sqrt();
             
-            if dist < 0.5 && test_pos.y >= 0.0 && test_pos.y <= tree.height {
+            if dist < 0.5 && test_pos.y >= tree.base_y && test_pos.y <= tree.base_y + tree.height {
                 return true;
             }
             
-            for layer in 0..tree.leaf_layers {
-                let layer_y = tree.height + layer as f32 * 0.8;
-                let size = (tree.leaf_layers - layer) as f32 * 0.8 + 1.5;
-                if dist < size / 2.0 && (test_pos.y - layer_y).abs() < 0.6 {
-                    return true;
-                }
+            let crown_y = tree.base_y + tree.height - tree.crown_radius.min(tree.height / 2.0);
+            let dy = test_pos.y - crown_y;
+            if dist * dist + dy * dy < tree.crown_radius * tree.crown_radius {
+                return true;
             }
         }
         
@@ -62,11 +117,7 @@ fn is_in_shadow(x: f32, y: f32, z: f32, trees: &Vec<Tree>, rocks: &Vec<Rock>, li
             let dz = test_pos.z - rock.z;
             let dist = (dx * dx + dz * dz).sqrt();
             
-            let rock_height = ((rock.x * 0.3).sin() + (rock.z * 0.3).cos()) * 2.5
-                + ((rock.x * 0.5).sin() * (rock.z * 0.5).cos()) * 1.5
-                + 2.0;
-            
-            if dist < rock.size / 2.0 && (test_pos.y - rock_height).abs() < rock.size {
+            if dist < rock.size / 2.0 && (test_pos.y - rock.y).abs() < rock.size {
                 return true;
             }
         }
@@ -92,71 +143,539 @@ fn apply_shadow(base_color: Color, in_shadow: bool) -> Color {
     }
 }
 
-fn main() {
-    println!("Iniciando Diorama Minecraft...");
-    
-    let (mut rl, thread) = raylib::init()
-        .size(1400, 900)
-        .title("Diorama Minecraft - Paisaje al Atardecer")
-        .build();
+// Tiñe un color con el brillo de la luz de los bloques (ver `LuzVertice::brillo`)
+fn iluminar(color: Color, brillo: &nalgebra::Vector3<f64>) -> Color {
+    Color::new(
+        (color.r as f64 * brillo.x).round() as u8,
+        (color.g as f64 * brillo.y).round() as u8,
+        (color.b as f64 * brillo.z).round() as u8,
+        color.a,
+    )
+}
+
+// Altura de la superficie respecto al nivel del mar (y = 0 en la vista raylib)
+fn terrain_height(terreno: &Mundo, nivel_mar: i32, x: f32, z: f32) -> f32 {
+    (terreno.superficie(x.round() as i32, z.round() as i32).unwrap_or(0) - nivel_mar) as f32
+}
+
+// Sube una malla de vóxeles a la GPU como modelo de raylib: dos triángulos por cara
+// y en cada vértice el color de su esquina (índice en `Cara::esquinas`)
+fn cargar_modelo_malla(rl: &mut RaylibHandle, thread: &RaylibThread, malla: &MallaVoxel,
+                       color_esquina: impl Fn(&Cara, usize) -> Color) -> Option<Model> {
+    if malla.caras.is_empty() {
+        return None;
+    }
+    let vertices = malla.caras.len() * 6;
     
-    rl.set_target_fps(60);
+    unsafe {
+        // raylib libera estos búferes con el modelo (UnloadModel)
+        let mut mesh: raylib::ffi::Mesh = std::mem::zeroed();
+        mesh.vertexCount = vertices as i32;
+        mesh.triangleCount = malla.triangulos() as i32;
+        mesh.vertices = raylib::ffi::MemAlloc((vertices * 3 * std::mem::size_of::<f32>()) as u32) as *mut f32;
+        mesh.normals = raylib::ffi::MemAlloc((vertices * 3 * std::mem::size_of::<f32>()) as u32) as *mut f32;
+        mesh.colors = raylib::ffi::MemAlloc((vertices * 4) as u32) as *mut u8;
+        let posiciones = std::slice::from_raw_parts_mut(mesh.vertices, vertices * 3);
+        let normales = std::slice::from_raw_parts_mut(mesh.normals, vertices * 3);
+        let colores = std::slice::from_raw_parts_mut(mesh.colors, vertices * 4);
+        
+        for (n, cara) in malla.caras.iter().enumerate() {
+            let esquinas = cara.esquinas();
+            let normal = cara.normal();
+            let colores_cara = [0, 1, 2, 3].map(|esquina| color_esquina(cara, esquina));
+            for (k, &esquina) in [0, 1, 2, 0, 2, 3].iter().enumerate() {
+                let v = n * 6 + k;
+                let p = esquinas[esquina];
+                let color = colores_cara[esquina];
+                posiciones[v * 3..v * 3 + 3].copy_from_slice(&[p.x as f32, p.y as f32, p.z as f32]);
+                normales[v * 3..v * 3 + 3].copy_from_slice(&[normal.x as f32, normal.y as f32, normal.z as f32]);
+                colores[v * 4..v * 4 + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+        
+        raylib::ffi::UploadMesh(&mut mesh, false);
+        rl.load_model_from_mesh(thread, Mesh::from_raw(mesh).make_weak()).ok()
+    }
+}
+
+// Sombra de la columna y luz de las esquinas de una cara de bloque
+type AtributoCara = (bool, [LuzVertice; 4]);
+
+// Iluminación horneada en los vértices del terreno: niveles de luz del mundo y cómo se
+// convierten en color
+struct LuzTerreno<'a> {
+    mapa: &'a MapaLuz,
+    config: &'a ConfiguracionLuz,
+}
+
+// Malla un chunk del terreno con la luz suave y la oclusión ambiental de cada esquina y
+// la sombra de su columna horneadas en el color de los vértices. El atributo de cada
+// cara de bloque es su entrada en la tabla de (sombra, luz de las esquinas), así solo
+// se fusionan caras que se ven igual. Devuelve el modelo (ninguno si el chunk no tiene
+// caras) y sus triángulos.
+fn cargar_modelo_chunk(rl: &mut RaylibHandle, thread: &RaylibThread, terreno: &Mundo, chunk: (i32, i32),
+                       luz: &LuzTerreno, sombra_columna: &impl Fn(i32, i32) -> bool) -> (Option<Model>, usize) {
+    let tabla: RefCell<(HashMap<AtributoCara, u32>, Vec<AtributoCara>)> = RefCell::default();
+    let malla = mallado::mallar_chunk(terreno, chunk, true, &|posicion, eje, positiva| {
+        let clave = (sombra_columna(posicion[0], posicion[2]), luz.mapa.luz_cara(terreno, posicion, eje, positiva));
+        let (indices, atributos) = &mut *tabla.borrow_mut();
+        *indices.entry(clave).or_insert_with(|| {
+            atributos.push(clave);
+            atributos.len() as u32 - 1
+        })
+    });
+    let atributos = tabla.into_inner().1;
+    let modelo = cargar_modelo_malla(rl, thread, &malla, |cara, esquina| {
+        let material = terreno.paleta.material(cara.id).expect("Bloque sin material");
+        let (sombra, luces) = atributos[cara.atributo as usize];
+        // Los bloques que emiten luz se ven con todo su brillo
+        let brillo = if material.es_emisivo() {
+            nalgebra::Vector3::repeat(1.0)
+        } else {
+            luces[esquina].brillo(luz.config)
+        };
+        let color = Color::new(
+            (material.albedo.x * brillo.x * 255.0).round() as u8,
+            (material.albedo.y * brillo.y * 255.0).round() as u8,
+            (material.albedo.z * brillo.z * 255.0).round() as u8,
+            ((1.0 - material.transparencia) * 255.0).round() as u8,
+        );
+        apply_shadow(color, sombra)
+    });
+    (modelo, malla.triangulos())
+}
+
+// Modelos de todos los chunks del terreno por coordenada de chunk y el total de triángulos
+fn cargar_modelos_terreno(rl: &mut RaylibHandle, thread: &RaylibThread, terreno: &Mundo, luz: &LuzTerreno,
+                          sombra_columna: impl Fn(i32, i32) -> bool) -> (BTreeMap<(i32, i32), Model>, usize) {
+    let mut triangulos = 0;
+    let mut modelos = BTreeMap::new();
+    for (chunk, _) in terreno.chunks() {
+        let (modelo, triangulos_chunk) = cargar_modelo_chunk(rl, thread, terreno, chunk, luz, &sombra_columna);
+        triangulos += triangulos_chunk;
+        if let Some(modelo) = modelo {
+            modelos.insert(chunk, modelo);
+        }
+    }
+    (modelos, triangulos)
+}
+
+// Vuelve a mallar los chunks indicados, por ejemplo tras un tick de la simulación
+fn recargar_chunks(rl: &mut RaylibHandle, thread: &RaylibThread, terreno: &Mundo, luz: &LuzTerreno,
+                   modelos: &mut BTreeMap<(i32, i32), Model>, chunks: impl IntoIterator<Item = (i32, i32)>,
+                   sombra_columna: impl Fn(i32, i32) -> bool) {
+    for chunk in chunks {
+        match cargar_modelo_chunk(rl, thread, terreno, chunk, luz, &sombra_columna).0 {
+            Some(modelo) => modelos.insert(chunk, modelo),
+            None => modelos.remove(&chunk),
+        };
+    }
+}
+
+// `diorama --simular [ticks]`: vierte una fuente de agua y una pila de grava en el centro
+// del terreno de la vista y avanza la simulación sin ventana
+fn simular_sin_ventana(ticks: u64) {
+    let mut terreno = escena::crear_mundo_minecraft_simple().mundo;
+    let mut simulacion = Simulacion::nueva(escena::REGION_DIORAMA.0, escena::REGION_DIORAMA.1);
+    simulacion.activar(&terreno);
+    let y = terreno.superficie(0, 0).unwrap_or(0);
+    simulacion.colocar(&mut terreno, (0, y + 3, 0), AGUA);
+    for dy in 4..7 {
+        simulacion.colocar(&mut terreno, (2, y + dy, 2), GRAVA);
+    }
     
-    let mut camera = Camera3D::perspective(
-        Vector3::new(30.0, 30.0, 50.0),
+    let agua_inicial = terreno.bloques().filter(|&(_, id)| id == AGUA).count();
+    let (mut cambios, mut caidos) = (0, 0);
+    while simulacion.ticks < ticks && simulacion.activa() {
+        let resumen = simulacion.paso(&mut terreno);
+        cambios += resumen.cambios.len();
+        caidos += resumen.caidos;
+    }
+    let agua = terreno.bloques().filter(|&(_, id)| id == AGUA).count();
+    println!("💧 {} ticks ({}): {} cambios, {} caídas; agua {} -> {} bloques",
+             simulacion.ticks, if simulacion.activa() { "aún activa" } else { "asentada" },
+             cambios, caidos, agua_inicial, agua);
+}
+
+// Cámara de la ventana: gira alrededor del centro a `zoom` de distancia y 30 de altura
+fn camara_orbita(angulo: f32, zoom: f32) -> Camera3D {
+    Camera3D::perspective(
+        Vector3::new(angulo.sin() * zoom, 30.0, angulo.cos() * zoom),
         Vector3::new(0.0, 0.0, 0.0),
         Vector3::new(0.0, 1.0, 0.0),
         45.0,
-    );
+    )
+}
+
+// Desplazamiento con el que raylib dibuja el mundo: cada columna va centrada en su
+// (x, z) y el nivel del mar queda en y = 0
+fn origen_vista(nivel_mar: i32) -> Vector3 {
+    Vector3::new(-0.5, -(nivel_mar + 1) as f32, -0.5)
+}
+
+// Proyecciones disponibles en la vista previa (tecla O)
+fn proyeccion_vista(indice: usize, zoom: f32) -> Proyeccion {
+    match indice % 4 {
+        0 => Proyeccion::Perspectiva,
+        1 => Proyeccion::Ortografica { ancho: zoom as f64 * 1.2 },
+        2 => Proyeccion::Equirectangular,
+        _ => Proyeccion::OjoDePez { campo_vision: 180.0 },
+    }
+}
+
+// Cámara del ray tracer equivalente a la de raylib. raylib dibuja el mundo desplazado
+// `origen`, así que la cámara se desplaza al revés para encuadrar los mismos bloques.
+fn camara_cpu(camera: &Camera3D, origen: Vector3, miniatura: bool, proyeccion: Proyeccion) -> Camara {
+    let en_mundo = |p: Vector3| Point3::new((p.x - origen.x) as f64, (p.y - origen.y) as f64, (p.z - origen.z) as f64);
+    let camara = Camara::nueva(
+        en_mundo(camera.position),
+        en_mundo(camera.target),
+        nalgebra::Vector3::new(camera.up.x as f64, camera.up.y as f64, camera.up.z as f64),
+        camera.fovy as f64,
+        ANCHO_VISTA_CPU as f64 / ALTO_VISTA_CPU as f64,
+    ).con_proyeccion(proyeccion);
     
-    let mut rng = rand::thread_rng();
-    let mut trees: Vec<Tree> = Vec::new();
+    // Efecto tilt-shift: lente abierto y plano focal inclinado sobre el objetivo
+    if miniatura {
+        camara.con_apertura(1.2).con_inclinacion(8.0)
+    } else {
+        camara
+    }
+}
+
+// Petición a la vista previa en CPU: (generacion, camara, integrador, reducir_ruido)
+type PeticionVista = Option<(u32, Camara, ModoRender, bool)>;
+// Imagen de la vista previa: (generacion, muestras, RGBA)
+type ImagenVista = (u32, u32, Vec<u8>);
+
+// Hilo que acumula pasadas del ray tracer en CPU. Recibe `Some((generacion, camara, modo,
+// reducir_ruido))` para reiniciar con una cámara o un integrador nuevos o `None` para
+// pausar, y devuelve (generacion, muestras, RGBA) tras cada pasada. Traza `mundo` hasta
+// que recibe otro (la ventana lo envía cuando la simulación o el corte lo cambian). Con
+// `luces` la escena se ilumina con las de un archivo de escena.
+fn lanzar_vista_cpu(mundo: Mundo, puente: Option<Posicion>, luces: Option<Vec<Luz>>)
+                    -> (Sender<PeticionVista>, Sender<Mundo>, Receiver<ImagenVista>) {
+    let (tx_camara, rx_camara) = mpsc::channel::<PeticionVista>();
+    let (tx_mundo, rx_mundo) = mpsc::channel::<Mundo>();
+    let (tx_imagen, rx_imagen) = mpsc::channel();
     
-    for _ in 0..35 {
-        let tree_type = match rng.gen_range(0..3) {
-            0 => TreeType::Oak,
-            1 => TreeType::Cherry,
-            _ => TreeType::Birch,
+    thread::spawn(move || {
+        let construir = |mundo: &Mundo| {
+            let mut escena = escena::crear_escena_diorama(mundo, puente);
+            if let Some(luces) = &luces {
+                escena.usar_luces(luces.clone());
+            }
+            escena
         };
+        let mut escena = construir(&mundo);
+        // Sobol sigue llenando el píxel de forma uniforme pasada tras pasada
+        let muestreo = Muestreador::nuevo(PatronMuestreo::Sobol, FiltroReconstruccion::mitchell(), 1);
+        let mut progresivo = RenderProgresivo::nuevo(ANCHO_VISTA_CPU, ALTO_VISTA_CPU).con_muestreo(muestreo);
+        let mut actual: PeticionVista = None;
+        // Pasadas de albedo y normal que guían la reducción de ruido de la cámara actual
+        let mut guias: Option<PasadasAuxiliares> = None;
         
-        trees.push(Tree {
-            x: rng.gen_range(-25.0..25.0),
-            z: rng.gen_range(-25.0..25.0),
-            height: rng.gen_range(8.0..15.0),
-            leaf_layers: rng.gen_range(3..6),
-            tree_type,
-        });
+        loop {
+            // En pausa se bloquea hasta recibir una cámara
+            if actual.is_none() {
+                match rx_camara.recv() {
+                    Ok(mensaje) => {
+                        actual = mensaje;
+                        progresivo.reiniciar();
+                        guias = None;
+                    }
+                    Err(_) => return,
+                }
+                continue;
+            }
+            
+            // Quedarse solo con el mundo y la cámara más recientes
+            if let Some(mundo) = rx_mundo.try_iter().last() {
+                escena = construir(&mundo);
+                progresivo.reiniciar();
+                guias = None;
+            }
+            while let Ok(mensaje) = rx_camara.try_recv() {
+                actual = mensaje;
+                progresivo.reiniciar();
+                guias = None;
+            }
+            
+            if let Some((generacion, camara, modo, reducir_ruido)) = &actual {
+                progresivo.agregar_pasada(&escena, camara, *modo);
+                let mut imagen = progresivo.imagen();
+                if *reducir_ruido {
+                    let guias = guias.get_or_insert_with(|| {
+                        PasadasAuxiliares::calcular(&escena, camara, ANCHO_VISTA_CPU, ALTO_VISTA_CPU)
+                    });
+                    imagen = reduccion_ruido::reducir_ruido(&imagen, guias, &ConfiguracionRuido::default());
+                }
+                let bytes = Revelado::default().a_rgba8(&imagen);
+                if tx_imagen.send((*generacion, progresivo.muestras, bytes)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    
+    (tx_camara, tx_mundo, rx_imagen)
+}
+
+// Valor que sigue a `--opcion` en la línea de comandos
+fn argumento<T: std::str::FromStr>(argumentos: &[String], opcion: &str) -> Option<T> {
+    argumentos
+        .iter()
+        .position(|a| a == opcion)
+        .and_then(|i| argumentos.get(i + 1))
+        .and_then(|valor| valor.parse().ok())
+}
+
+// `diorama --secuencia <directorio> [--muestras N] [--patron aleatorio|estratificado|halton|sobol]
+//          [--filtro caja|tienda|gaussiano|mitchell] [--ruido umbral] [--tiempo segundos]
+//          [--modo caminos|whitted] [--exposicion EV] [--tonos aces|reinhard|lineal] [--exr]
+//          [--luces archivo.luces] [--entorno mapa.hdr] [--rotacion_entorno grados]
+//          [--escala_entorno factor] [--pasadas] [--reducir_ruido] [--corte [z]]`:
+// vuelta completa con el ray tracer en CPU, sin ventana. Con `--ruido` o `--tiempo` el
+// muestreo es adaptativo y `--muestras` pasa a ser el mínimo por píxel. `--patron` y
+// `--filtro` eligen cómo se reparten las muestras en el píxel (Sobol por defecto) y cómo
+// se reconstruye la imagen (Mitchell por defecto). `--modo whitted`
+// usa el integrador rápido en lugar del trazado de caminos. `--luces` cambia
+// el sol, el cielo y los focos por los del archivo (el entorno, si se da, sustituye
+// después al sol y al cielo). `--corte` quita la parte del diorama con z mayor o igual
+// que `z` (0 por defecto) para ver el subsuelo. La vuelta empieza donde la ventana
+// abre la cámara y recorre el mismo mundo que la ventana.
+fn renderizar_giro_cpu(directorio: &str, argumentos: &[String]) {
+    let diorama = escena::crear_mundo_minecraft_simple();
+    let mundo = if argumentos.iter().any(|a| a == "--corte") {
+        diorama.mundo.cortado(2, argumento(argumentos, "--corte").unwrap_or(0))
+    } else {
+        diorama.mundo
+    };
+    let mut escena = escena::crear_escena_diorama(&mundo, diorama.puente);
+    if let Some(ruta) = argumento::<String>(argumentos, "--luces") {
+        match iluminacion::cargar_luces(&ruta) {
+            Ok(luces) => escena.usar_luces(luces),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                return;
+            }
+        }
     }
+    if let Some(ruta) = argumento::<String>(argumentos, "--entorno") {
+        match entorno::MapaEntorno::cargar(&ruta) {
+            Ok(mapa) => {
+                let mapa = mapa.con_rotacion(argumento(argumentos, "--rotacion_entorno").unwrap_or(0.0));
+                escena.usar_entorno(mapa, argumento(argumentos, "--escala_entorno").unwrap_or(1.0));
+            }
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                return;
+            }
+        }
+    }
+    
+    let muestras_por_pixel = argumento(argumentos, "--muestras").unwrap_or(16);
+    let umbral_ruido: Option<f64> = argumento(argumentos, "--ruido");
+    let tiempo_limite: Option<f64> = argumento(argumentos, "--tiempo");
+    let adaptativo = (umbral_ruido.is_some() || tiempo_limite.is_some()).then(|| ConfiguracionAdaptativa {
+        umbral_ruido: umbral_ruido.unwrap_or(ConfiguracionAdaptativa::default().umbral_ruido),
+        muestras_minimas: muestras_por_pixel,
+        tiempo_limite: tiempo_limite.map(std::time::Duration::from_secs_f64),
+        ..Default::default()
+    });
+    let modo = match argumento::<String>(argumentos, "--modo").as_deref() {
+        Some("whitted") => ModoRender::Whitted,
+        _ => ModoRender::TrazadoCaminos,
+    };
+    let patron = match argumento::<String>(argumentos, "--patron").as_deref() {
+        Some("aleatorio") => PatronMuestreo::Aleatorio,
+        Some("estratificado") => PatronMuestreo::Estratificado,
+        Some("halton") => PatronMuestreo::Halton,
+        _ => PatronMuestreo::Sobol,
+    };
+    let filtro = match argumento::<String>(argumentos, "--filtro").as_deref() {
+        Some("caja") => FiltroReconstruccion::Caja,
+        Some("tienda") => FiltroReconstruccion::tienda(),
+        Some("gaussiano") => FiltroReconstruccion::gaussiano(),
+        _ => FiltroReconstruccion::mitchell(),
+    };
+    let operador = match argumento::<String>(argumentos, "--tonos").as_deref() {
+        Some("reinhard") => OperadorTonos::Reinhard { blanco: 4.0 },
+        Some("lineal") => OperadorTonos::Lineal,
+        _ => OperadorTonos::Aces,
+    };
+    
+    // Mismo encuadre y proporción que la ventana (y su vista previa)
+    let camara = camara_cpu(&camara_orbita(0.0, ZOOM_INICIAL), origen_vista(diorama.nivel_mar), false, Proyeccion::Perspectiva);
+    let trayectoria = TrayectoriaCamara::giratoria(&camara, 1.0, DURACION_GIRO);
+    let config = ConfiguracionSecuencia {
+        ancho: 420,
+        alto: 270,
+        cuadros_por_segundo: CUADROS_POR_SEGUNDO,
+        // En modo adaptativo cada pasada es corta para poder parar a tiempo
+        muestreo: Muestreador::nuevo(
+            patron,
+            filtro,
+            if adaptativo.is_some() { 4 } else { muestras_por_pixel },
+        ),
+        modo,
+        adaptativo,
+        revelado: Revelado::nuevo(argumento(argumentos, "--exposicion").unwrap_or(0.0), operador),
+        exportar_exr: argumentos.iter().any(|a| a == "--exr"),
+        pasadas: argumentos.iter().any(|a| a == "--pasadas"),
+        reducir_ruido: argumentos.iter().any(|a| a == "--reducir_ruido").then(ConfiguracionRuido::default),
+    };
     
-    let mut rocks: Vec<Rock> = Vec::new();
-    for _ in 0..20 {
-        rocks.push(Rock {
-            x: rng.gen_range(-25.0..25.0),
-            z: rng.gen_range(-25.0..25.0),
-            size: rng.gen_range(2.0..5.0),
-        });
+    match animacion::renderizar_secuencia(&escena, &trayectoria, &config, directorio) {
+        Ok(cuadros) => println!("✅ Secuencia lista: {} cuadros en {}", cuadros, directorio),
+        Err(e) => eprintln!("❌ Error al renderizar la secuencia: {}", e),
     }
+}
+
+// Color de pantalla del cielo físico en una dirección (mismo revelado que el ray tracer)
+fn color_cielo(cielo: &CieloPreetham, direccion: nalgebra::Vector3<f64>) -> Color {
+    let bytes = Revelado::default().a_rgba8(&[cielo.radiancia(&direccion)]);
+    Color::new(bytes[0], bytes[1], bytes[2], 255)
+}
+
+fn main() {
+    let argumentos: Vec<String> = std::env::args().collect();
+    if let Some(indice) = argumentos.iter().position(|a| a == "--secuencia") {
+        let directorio = argumentos.get(indice + 1).map(String::as_str).filter(|d| !d.starts_with("--")).unwrap_or(DIRECTORIO_CUADROS);
+        renderizar_giro_cpu(directorio, &argumentos);
+        return;
+    }
+    if argumentos.iter().any(|a| a == "--simular") {
+        simular_sin_ventana(argumento(&argumentos, "--simular").unwrap_or(200));
+        return;
+    }
+    
+    println!("Iniciando Diorama Minecraft...");
+    
+    let (mut rl, thread) = raylib::init()
+        .size(1400, 900)
+        .title("Diorama Minecraft - Paisaje al Atardecer")
+        .build();
+    
+    rl.set_target_fps(60);
+    
+    let mut camera = camara_orbita(0.0, ZOOM_INICIAL);
+    
+    // El mundo del diorama, el mismo que trazan la vista previa en CPU y `--secuencia`.
+    // Las rocas y las flores solo se dibujan aquí; su tamaño y color salen de la semilla.
+    let diorama = escena::crear_mundo_minecraft_simple();
+    let nivel_mar = diorama.nivel_mar;
+    let puente = diorama.puente;
+    let semilla = escena::SEMILLA_DIORAMA;
+    let mut terreno = diorama.mundo;
     
-    let mut flowers: Vec<Flower> = Vec::new();
-    for _ in 0..50 {
-        flowers.push(Flower {
-            x: rng.gen_range(-25.0..25.0),
-            z: rng.gen_range(-25.0..25.0),
-            color: if rng.gen_bool(0.5) {
+    let rocks: Vec<Rock> = diorama.rocas
+        .iter()
+        .map(|sitio| {
+            let size = 2.0 + 3.0 * aleatorio_celda(semilla.wrapping_add(5), sitio.x, sitio.z) as f32;
+            Rock {
+                x: sitio.x as f32,
+                z: sitio.z as f32,
+                y: (sitio.y - nivel_mar) as f32 + size * 0.6,
+                size,
+            }
+        })
+        .collect();
+    
+    let flowers: Vec<Flower> = diorama.flores
+        .iter()
+        .map(|sitio| Flower {
+            x: sitio.x as f32,
+            z: sitio.z as f32,
+            y: (sitio.y - nivel_mar) as f32,
+            color: if aleatorio_celda(semilla.wrapping_add(6), sitio.x, sitio.z) < 0.5 {
                 Color::new(255, 50, 50, 255)
             } else {
                 Color::new(255, 255, 50, 255)
             },
-        });
-    }
+        })
+        .collect();
+    
+    let trees: Vec<Tree> = diorama.arboles
+        .iter()
+        .map(|((x, y, z), arbol)| Tree {
+            x: *x as f32,
+            z: *z as f32,
+            base_y: (y - 1 - nivel_mar) as f32,
+            height: arbol.alto as f32,
+            crown_radius: arbol.radio_copa as f32,
+        })
+        .collect();
     
     let light_dir = Vector3::new(-0.5, -0.7, -0.3).normalized();
     
+    // Terreno mallado con los árboles: caras ocultas descartadas y caras coplanares fusionadas.
+    // La sombra de árboles y rocas se guarda por columna y se hornea en el color.
+    let sombra_columna = |terreno: &Mundo, x: i32, z: i32| {
+        let (fx, fz) = (x as f32, z as f32);
+        is_in_shadow(fx, terrain_height(terreno, nivel_mar, fx, fz), fz, &trees, &rocks, light_dir)
+    };
+    // Luz del cielo y de los bloques (de día o de noche con la tecla N)
+    let mut config_luz_bloques = ConfiguracionLuz::default();
+    let mut noche = false;
+    let mut mapa_luz = MapaLuz::calcular(&terreno);
+    let luz = LuzTerreno { mapa: &mapa_luz, config: &config_luz_bloques };
+    let (mut modelos_terreno, triangulos_terreno) =
+        cargar_modelos_terreno(&mut rl, &thread, &terreno, &luz, |x, z| sombra_columna(&terreno, x, z));
+    // Corte por z = 0 que deja ver las cuevas (tecla C), iluminado como queda tras cortar
+    let cortado = terreno.cortado(2, 0);
+    let luz = LuzTerreno { mapa: &MapaLuz::calcular(&cortado), config: &config_luz_bloques };
+    let (mut modelos_corte, _) =
+        cargar_modelos_terreno(&mut rl, &thread, &cortado, &luz, |x, z| sombra_columna(&terreno, x, z));
+    let mut corte = false;
+    // Agua que corre y arena y grava que caen, a ticks fijos. Los chunks que cambian se
+    // vuelven a mallar; los del corte, solo cuando se muestra.
+    let mut simulacion = Simulacion::nueva(escena::REGION_DIORAMA.0, escena::REGION_DIORAMA.1);
+    simulacion.activar(&terreno);
+    let mut tiempo_simulacion: f32 = 0.0;
+    let mut chunks_corte_viejos: BTreeSet<(i32, i32)> = BTreeSet::new();
+    println!("🔺 Terreno raylib: {} triángulos como cubos -> {} caras expuestas -> {} fusionados",
+             mallado::triangulos_cubos(&terreno), mallado::mallar(&terreno, false).triangulos(), triangulos_terreno);
+    let origen_terreno = origen_vista(nivel_mar);
+    let config_luz = iluminacion::ConfiguracionIluminacion::default();
+    let cielo = CieloPreetham::nuevo(config_luz.direccion_sol, config_luz.turbidez);
+    
     let mut rotation_angle: f32 = 0.0;
-    let mut zoom: f32 = 50.0;
+    let mut zoom = ZOOM_INICIAL;
     let mut is_dragging = false;
     let mut last_mouse_pos = Vector2::zero();
     
+    // Vista previa progresiva del ray tracer en CPU (tecla P) de este mismo mundo, con las
+    // luces de `--luces`. Cuando la simulación o el corte cambian el mundo se le envía de nuevo.
+    let luces = argumento::<String>(&argumentos, "--luces").and_then(|ruta| match iluminacion::cargar_luces(&ruta) {
+        Ok(luces) => Some(luces),
+        Err(e) => {
+            eprintln!("❌ {} (se usa la iluminación por defecto)", e);
+            None
+        }
+    });
+    let (tx_camara_cpu, tx_mundo_cpu, rx_imagen_cpu) = lanzar_vista_cpu(terreno.clone(), puente, luces);
+    let mut mundo_cpu_viejo = false;
+    let imagen_vacia = Image::gen_image_color(ANCHO_VISTA_CPU as i32, ALTO_VISTA_CPU as i32, Color::BLACK);
+    let mut textura_cpu = rl
+        .load_texture_from_image(&thread, &imagen_vacia)
+        .expect("No se pudo crear la textura de la vista previa");
+    textura_cpu.set_texture_filter(&thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
+    let mut vista_cpu = false;
+    let mut miniatura = false;
+    let mut reducir_ruido = false;
+    let mut modo_cpu = ModoRender::TrazadoCaminos;
+    let mut indice_proyeccion: usize = 0;
+    let mut generacion_cpu: u32 = 0;
+    let mut muestras_cpu: u32 = 0;
+    let mut hay_imagen_cpu = false;
+    // Cámara de la última imagen CPU: posición, objetivo y campo de visión
+    let vista_de = |c: &Camera3D| [c.position.x, c.position.y, c.position.z, c.target.x, c.target.y, c.target.z, c.fovy];
+    let mut ultima_vista = vista_de(&camera);
+    
+    // Grabación de la vista raylib a paso fijo (tecla G): trayectoria y cuadro actual
+    let mut grabacion: Option<(TrayectoriaCamara, usize)> = None;
+    
     while !rl.window_should_close() {
         if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
             is_dragging = true;
@@ -180,115 +699,228 @@ fn main() {
             zoom = zoom.clamp(20.0, 100.0);
         }
         
-        camera.position = Vector3::new(
-            rotation_angle.sin() * zoom,
-            30.0,
-            rotation_angle.cos() * zoom,
-        );
-        camera.target = Vector3::new(0.0, 0.0, 0.0);
+        camera = camara_orbita(rotation_angle, zoom);
         
-        let mut d = rl.begin_drawing(&thread);
-        d.clear_background(Color::new(135, 206, 235, 255));
+        if rl.is_key_pressed(KeyboardKey::KEY_G) && grabacion.is_none() {
+            match std::fs::create_dir_all(DIRECTORIO_CUADROS) {
+                Ok(()) => {
+                    let camara = camara_cpu(&camera, origen_terreno, false, Proyeccion::Perspectiva);
+                    grabacion = Some((TrayectoriaCamara::giratoria(&camara, 1.0, DURACION_GIRO), 0));
+                }
+                Err(e) => eprintln!("❌ No se pudo crear {}: {}", DIRECTORIO_CUADROS, e),
+            }
+        }
         
-        let mut d3 = d.begin_mode3D(camera);
+        // Durante la grabación la cámara sigue la trayectoria, no el ratón. La trayectoria
+        // está en coordenadas del mundo, como la de `--secuencia`.
+        if let Some((trayectoria, cuadro)) = &grabacion {
+            if let Some(clave) = trayectoria.evaluar(*cuadro as f64 / CUADROS_POR_SEGUNDO) {
+                let en_vista = |p: Point3<f64>| Vector3::new(p.x as f32, p.y as f32, p.z as f32) + origen_terreno;
+                camera.position = en_vista(clave.posicion);
+                camera.target = en_vista(clave.objetivo);
+                camera.fovy = clave.campo_vision as f32;
+            }
+        }
         
-        for x in -25..25 {
-            for z in -25..25 {
-                let fx = x as f32;
-                let fz = z as f32;
-                let height = ((fx * 0.1).sin() + (fz * 0.1).cos()) * 1.5;
-                
-                let in_shadow = is_in_shadow(fx, height, fz, &trees, &rocks, light_dir);
-                
-                let river_center = 0.0;
-                let river_width = 4.0;
-                let distance_to_river = (fz - river_center).abs();
-                
-                if distance_to_river < river_width {
-                    let water_color = Color::new(30, 144, 255, 180);
-                    let water_y = -0.5;
-                    d3.draw_cube(
-                        Vector3::new(fx, water_y, fz),
-                        2.0, 0.3, 2.0,
-                        apply_shadow(water_color, in_shadow)
-                    );
-                } else {
-                    let grass_color = if distance_to_river < river_width + 2.0 {
-                        Color::new(34, 139, 34, 255)
-                    } else {
-                        Color::new(50, 205, 50, 255)
-                    };
-                    
-                    d3.draw_cube(
-                        Vector3::new(fx, height - 1.0, fz),
-                        2.0, 2.0, 2.0,
-                        apply_shadow(grass_color, in_shadow)
-                    );
+        // Reiniciar la acumulación cuando cambia la cámara o se activa la vista
+        let activar_cpu = rl.is_key_pressed(KeyboardKey::KEY_P);
+        if activar_cpu {
+            vista_cpu = !vista_cpu;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_M) {
+            miniatura = !miniatura;
+            ultima_vista = [f32::NAN; 7];
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_O) {
+            indice_proyeccion += 1;
+            ultima_vista = [f32::NAN; 7];
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_D) {
+            reducir_ruido = !reducir_ruido;
+            ultima_vista = [f32::NAN; 7];
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_W) {
+            modo_cpu = match modo_cpu {
+                ModoRender::TrazadoCaminos => ModoRender::Whitted,
+                ModoRender::Whitted => ModoRender::TrazadoCaminos,
+            };
+            ultima_vista = [f32::NAN; 7];
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_C) {
+            corte = !corte;
+            mundo_cpu_viejo = true;
+        }
+        let mut chunks_cambiados = BTreeSet::new();
+        if rl.is_key_pressed(KeyboardKey::KEY_N) {
+            noche = !noche;
+            config_luz_bloques = if noche { ConfiguracionLuz::noche() } else { ConfiguracionLuz::default() };
+            chunks_cambiados.extend(terreno.chunks().map(|(chunk, _)| chunk));
+        }
+        
+        // Fuente de agua (F) o grava (V) unos bloques sobre el centro del terreno
+        for (tecla, bloque, altura) in [(KeyboardKey::KEY_F, AGUA, 3), (KeyboardKey::KEY_V, GRAVA, 6)] {
+            if rl.is_key_pressed(tecla) {
+                let y = terreno.superficie(0, 0).unwrap_or(0) + altura;
+                simulacion.colocar(&mut terreno, (0, y, 0), bloque);
+            }
+        }
+        tiempo_simulacion = (tiempo_simulacion + rl.get_frame_time()).min(1.0);
+        let mut mundo_cambiado = false;
+        while tiempo_simulacion >= 1.0 / TICKS_POR_SEGUNDO {
+            tiempo_simulacion -= 1.0 / TICKS_POR_SEGUNDO;
+            for (x, _, z) in simulacion.paso(&mut terreno).cambios {
+                // Un bloque en el borde también cambia las caras visibles del chunk vecino
+                for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    chunks_cambiados.insert(Mundo::chunk_de(x + dx, z + dz));
                 }
+                mundo_cambiado = true;
             }
         }
+        if mundo_cambiado {
+            // La luz cambia hasta 15 bloques más allá: se rehacen también los chunks
+            // donde cambió y sus vecinos, cuyas caras del borde la leen
+            let nuevo_mapa = MapaLuz::calcular(&terreno);
+            for (cx, cz) in nuevo_mapa.chunks_distintos(&mapa_luz) {
+                for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    chunks_cambiados.insert((cx + dx, cz + dz));
+                }
+            }
+            mapa_luz = nuevo_mapa;
+        }
+        let luz = LuzTerreno { mapa: &mapa_luz, config: &config_luz_bloques };
+        recargar_chunks(&mut rl, &thread, &terreno, &luz, &mut modelos_terreno, chunks_cambiados.iter().copied(),
+                        |x, z| sombra_columna(&terreno, x, z));
+        chunks_corte_viejos.extend(chunks_cambiados);
+        if corte && !chunks_corte_viejos.is_empty() {
+            let cortado = terreno.cortado(2, 0);
+            let luz = LuzTerreno { mapa: &MapaLuz::calcular(&cortado), config: &config_luz_bloques };
+            recargar_chunks(&mut rl, &thread, &cortado, &luz, &mut modelos_corte,
+                            std::mem::take(&mut chunks_corte_viejos), |x, z| sombra_columna(&terreno, x, z));
+        }
+        // Árboles, rocas y flores solo reciben la luz del cielo
+        let luz_objetos = LuzVertice { cielo: 4 * LUZ_MAXIMA, bloques: 0, oclusion: 0 }.brillo(&config_luz_bloques);
+        // Se compara la cámara real: durante la grabación la mueve la trayectoria
+        let camara_movida = vista_de(&camera) != ultima_vista;
+        ultima_vista = vista_de(&camera);
+        mundo_cpu_viejo |= mundo_cambiado;
+        let mundo_enviado = vista_cpu && mundo_cpu_viejo;
+        if mundo_enviado {
+            tx_mundo_cpu.send(if corte { terreno.cortado(2, 0) } else { terreno.clone() }).ok();
+            mundo_cpu_viejo = false;
+        }
         
-        for tree in &trees {
-            let trunk_color = match tree.tree_type {
-                TreeType::Oak => Color::new(139, 69, 19, 255),
-                TreeType::Cherry => Color::new(160, 82, 45, 255),
-                TreeType::Birch => Color::new(245, 245, 220, 255),
-            };
-            
-            let trunk_in_shadow = is_in_shadow(tree.x, tree.height / 2.0, tree.z, &trees, &rocks, light_dir);
-            d3.draw_cube(
-                Vector3::new(tree.x, tree.height / 2.0, tree.z),
-                1.0, tree.height, 1.0,
-                apply_shadow(trunk_color, trunk_in_shadow)
+        if vista_cpu && (activar_cpu || camara_movida || mundo_enviado) {
+            generacion_cpu += 1;
+            hay_imagen_cpu = false;
+            muestras_cpu = 0;
+            let camara = camara_cpu(&camera, origen_terreno, miniatura, proyeccion_vista(indice_proyeccion, zoom));
+            tx_camara_cpu.send(Some((generacion_cpu, camara, modo_cpu, reducir_ruido))).ok();
+        } else if activar_cpu {
+            tx_camara_cpu.send(None).ok();
+        }
+        
+        let mut ultima_imagen = None;
+        while let Ok((generacion, muestras, bytes)) = rx_imagen_cpu.try_recv() {
+            if generacion == generacion_cpu {
+                muestras_cpu = muestras;
+                ultima_imagen = Some(bytes);
+            }
+        }
+        if let Some(bytes) = ultima_imagen {
+            if textura_cpu.update_texture(&bytes).is_ok() {
+                hay_imagen_cpu = true;
+            }
+        }
+        
+        let mut d = rl.begin_drawing(&thread);
+        // Fondo con el cielo de Preetham: cenit arriba, horizonte hacia donde mira la cámara
+        let mirada = camera.target - camera.position;
+        let acimut = nalgebra::Vector3::new(mirada.x as f64, 0.0, mirada.z as f64)
+            .try_normalize(1e-9)
+            .unwrap_or(nalgebra::Vector3::z());
+        let color_horizonte = iluminar(color_cielo(&cielo, acimut + nalgebra::Vector3::new(0.0, 0.05, 0.0)), &luz_objetos);
+        let color_alto = iluminar(color_cielo(&cielo, acimut + nalgebra::Vector3::new(0.0, 1.5, 0.0)), &luz_objetos);
+        d.clear_background(color_horizonte);
+        let (ancho, alto) = (d.get_screen_width(), d.get_screen_height());
+        d.draw_rectangle_gradient_v(0, 0, ancho, alto, color_alto, color_horizonte);
+        
+        if vista_cpu && hay_imagen_cpu {
+            let ancho_pantalla = d.get_screen_width() as f32;
+            let alto_pantalla = d.get_screen_height() as f32;
+            d.draw_texture_pro(
+                &textura_cpu,
+                Rectangle::new(0.0, 0.0, ANCHO_VISTA_CPU as f32, ALTO_VISTA_CPU as f32),
+                Rectangle::new(0.0, 0.0, ancho_pantalla, alto_pantalla),
+                Vector2::zero(),
+                0.0,
+                Color::WHITE,
             );
+        } else {
+            let mut d3 = d.begin_mode3D(camera);
+        
+            // Con el corte desaparece también lo que estaba sobre la mitad quitada
+            let visible = |z: f32| !corte || z < -0.5;
+            let modelos = if corte { &modelos_corte } else { &modelos_terreno };
+            for modelo in modelos.values() {
+                d3.draw_model(modelo, origen_terreno, 1.0, Color::WHITE);
+            }
+        
+            for rock in rocks.iter().filter(|rock| visible(rock.z)) {
+                let rock_in_shadow = is_in_shadow(rock.x, rock.y, rock.z, &trees, &rocks, light_dir);
+                d3.draw_cube(
+                    Vector3::new(rock.x, rock.y, rock.z),
+                    rock.size, rock.size * 1.2, rock.size,
+                    iluminar(apply_shadow(Color::new(128, 128, 128, 255), rock_in_shadow), &luz_objetos)
+                );
+            }
+        
+            for flower in flowers.iter().filter(|flower| visible(flower.z)) {
+                let flower_y = flower.y + 0.5;
+                let flower_in_shadow = is_in_shadow(flower.x, flower_y, flower.z, &trees, &rocks, light_dir);
+            
+                d3.draw_cube(
+                    Vector3::new(flower.x, flower_y, flower.z),
+                    0.3, 1.0, 0.3,
+                    iluminar(apply_shadow(Color::new(34, 139, 34, 255), flower_in_shadow), &luz_objetos)
+                );
             
-            for layer in 0..tree.leaf_layers {
-                let layer_y = tree.height + layer as f32 * 0.8;
-                let size = (tree.leaf_layers - layer) as f32 * 0.8 + 1.5;
-                
-                let leaf_color = match tree.tree_type {
-                    TreeType::Oak => Color::new(34, 139, 34, 255),
-                    TreeType::Cherry => Color::new(255, 182, 193, 255),
-                    TreeType::Birch => Color::new(144, 238, 144, 255),
-                };
-                
-                let leaf_in_shadow = is_in_shadow(tree.x, layer_y, tree.z, &trees, &rocks, light_dir);
                 d3.draw_cube(
-                    Vector3::new(tree.x, layer_y, tree.z),
-                    size, size * 0.8, size,
-                    apply_shadow(leaf_color, leaf_in_shadow)
+                    Vector3::new(flower.x, flower_y + 0.5, flower.z),
+                    0.5, 0.5, 0.5,
+                    iluminar(apply_shadow(flower.color, flower_in_shadow), &luz_objetos)
                 );
             }
         }
         
-        for rock in &rocks {
-            let rock_height = ((rock.x * 0.3).sin() + (rock.z * 0.3).cos()) * 2.5
-                + ((rock.x * 0.5).sin() * (rock.z * 0.5).cos()) * 1.5
-                + 2.0;
+        // Se lee el framebuffer antes de dibujar textos de estado para no grabarlos
+        let mut terminada = false;
+        if let Some((trayectoria, cuadro)) = &mut grabacion {
+            unsafe { raylib::ffi::rlDrawRenderBatchActive() };
+            let ruta = format!("{}/raylib_{:04}.png", DIRECTORIO_CUADROS, *cuadro + 1);
+            d.load_image_from_screen(&thread).export_image(&ruta);
             
-            let rock_in_shadow = is_in_shadow(rock.x, rock_height, rock.z, &trees, &rocks, light_dir);
-            d3.draw_cube(
-                Vector3::new(rock.x, rock_height, rock.z),
-                rock.size, rock.size * 1.2, rock.size,
-                apply_shadow(Color::new(128, 128, 128, 255), rock_in_shadow)
-            );
+            *cuadro += 1;
+            let total = trayectoria.numero_cuadros(CUADROS_POR_SEGUNDO);
+            terminada = *cuadro >= total;
+            if terminada {
+                println!("🎞️ Grabados {} cuadros en {}/", total, DIRECTORIO_CUADROS);
+            }
+        }
+        if terminada {
+            grabacion = None;
         }
         
-        for flower in &flowers {
-            let flower_y = ((flower.x * 0.1).sin() + (flower.z * 0.1).cos()) * 1.5 + 0.5;
-            let flower_in_shadow = is_in_shadow(flower.x, flower_y, flower.z, &trees, &rocks, light_dir);
-            
-            d3.draw_cube(
-                Vector3::new(flower.x, flower_y, flower.z),
-                0.3, 1.0, 0.3,
-                apply_shadow(Color::new(34, 139, 34, 255), flower_in_shadow)
-            );
-            
-            d3.draw_cube(
-                Vector3::new(flower.x, flower_y + 0.5, flower.z),
-                0.5, 0.5, 0.5,
-                apply_shadow(flower.color, flower_in_shadow)
-            );
+        if vista_cpu {
+            let integrador = match modo_cpu {
+                ModoRender::TrazadoCaminos => "caminos",
+                ModoRender::Whitted => "Whitted",
+            };
+            let estado = if hay_imagen_cpu {
+                format!("Ray tracer CPU ({}): {} muestras", integrador, muestras_cpu)
+            } else {
+                format!("Ray tracer CPU ({}): renderizando...", integrador)
+            };
+            d.draw_text(&estado, 10, 10, 20, Color::WHITE);
         }
     }
 }
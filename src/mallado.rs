@@ -0,0 +1,186 @@
+use nalgebra::{Point3, Vector3};
+use crate::mundo::{IdBloque, Mundo, AIRE, TAMANO_CHUNK};
+
+/// Cara rectangular alineada a los ejes que cubre una o más caras de bloque
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cara {
+    pub eje: usize,            // Eje de la normal (0 = x, 1 = y, 2 = z)
+    pub positiva: bool,        // La normal apunta hacia +eje
+    pub origen: [i32; 3],      // Esquina mínima del rectángulo (sobre el plano de la cara)
+    pub ancho: i32,            // Bloques a lo largo del eje (eje + 1) % 3
+    pub alto: i32,             // Bloques a lo largo del eje (eje + 2) % 3
+    pub id: IdBloque,
+    pub atributo: u32,         // Dato extra por cara de bloque; solo se fusionan caras iguales
+}
+
+impl Cara {
+    /// Ejes del plano (u, v) con u × v = +eje
+    pub fn ejes(&self) -> (usize, usize) {
+        ((self.eje + 1) % 3, (self.eje + 2) % 3)
+    }
+
+    pub fn normal(&self) -> Vector3<f64> {
+        let mut normal = Vector3::zeros();
+        normal[self.eje] = if self.positiva { 1.0 } else { -1.0 };
+        normal
+    }
+
+    /// Esquinas en sentido antihorario vistas desde fuera
+    pub fn esquinas(&self) -> [Point3<f64>; 4] {
+        let (u, v) = self.ejes();
+        let origen = Point3::new(self.origen[0] as f64, self.origen[1] as f64, self.origen[2] as f64);
+        let mut du = Vector3::zeros();
+        let mut dv = Vector3::zeros();
+        du[u] = self.ancho as f64;
+        dv[v] = self.alto as f64;
+
+        let esquinas = [origen, origen + du, origen + du + dv, origen + dv];
+        if self.positiva {
+            esquinas
+        } else {
+            [esquinas[0], esquinas[3], esquinas[2], esquinas[1]]
+        }
+    }
+
+    /// Esquinas mínima y máxima del rectángulo
+    pub fn limites(&self) -> (Point3<f64>, Point3<f64>) {
+        let esquinas = self.esquinas();
+        (esquinas[0].inf(&esquinas[2]), esquinas[0].sup(&esquinas[2]))
+    }
+}
+
+/// Caras visibles de un trozo del mundo
+#[derive(Clone, Default)]
+pub struct MallaVoxel {
+    pub caras: Vec<Cara>,
+}
+
+impl MallaVoxel {
+    pub fn triangulos(&self) -> usize {
+        self.caras.len() * 2
+    }
+
+    pub fn extender(&mut self, otra: MallaVoxel) {
+        self.caras.extend(otra.caras);
+    }
+}
+
+/// Triángulos necesarios para dibujar cada bloque como un cubo completo
+pub fn triangulos_cubos(mundo: &Mundo) -> usize {
+    mundo.numero_bloques() * 12
+}
+
+// Una cara es visible si el vecino no la tapa: el aire nunca tapa, un bloque igual
+// sí (agua contra agua) salvo que tenga huecos, y uno distinto solo si es opaco
+fn cara_visible(mundo: &Mundo, id: IdBloque, vecino: IdBloque) -> bool {
+    if vecino == AIRE {
+        return true;
+    }
+    let material = match mundo.paleta.material(vecino) {
+        Some(material) => material,
+        None => return true,
+    };
+    if vecino == id {
+        return material.recorte_alfa.is_some();
+    }
+    material.transparencia > 0.0 || material.recorte_alfa.is_some()
+}
+
+/// Malla de todo el mundo (ver `mallar_chunk`)
+pub fn mallar(mundo: &Mundo, voraz: bool) -> MallaVoxel {
+    let mut malla = MallaVoxel::default();
+    for (clave, _) in mundo.chunks().filter(|(_, chunk)| !chunk.esta_vacio()) {
+        malla.extender(mallar_chunk(mundo, clave, voraz, &|_, _, _| 0));
+    }
+    malla
+}
+
+/// Caras expuestas de los bloques de un chunk. Con `voraz` las caras coplanares
+/// contiguas con el mismo bloque y el mismo `atributo(posicion, eje, positiva)` se
+/// fusionan en rectángulos lo más grandes posible.
+pub fn mallar_chunk<F>(mundo: &Mundo, chunk: (i32, i32), voraz: bool, atributo: &F) -> MallaVoxel
+where
+    F: Fn([i32; 3], usize, bool) -> u32,
+{
+    let minimo = [chunk.0 * TAMANO_CHUNK, 0, chunk.1 * TAMANO_CHUNK];
+    let tamano = [TAMANO_CHUNK, mundo.altura as i32, TAMANO_CHUNK];
+    let mut malla = MallaVoxel::default();
+
+    for eje in 0..3 {
+        let (u, v) = ((eje + 1) % 3, (eje + 2) % 3);
+        let (ancho, alto) = (tamano[u] as usize, tamano[v] as usize);
+        let mut mascara: Vec<Option<(IdBloque, u32)>> = vec![None; ancho * alto];
+
+        for positiva in [false, true] {
+            let paso = if positiva { 1 } else { -1 };
+
+            for capa in 0..tamano[eje] {
+                // Caras expuestas de esta capa de bloques
+                for j in 0..alto {
+                    for i in 0..ancho {
+                        let mut posicion = [0; 3];
+                        posicion[eje] = minimo[eje] + capa;
+                        posicion[u] = minimo[u] + i as i32;
+                        posicion[v] = minimo[v] + j as i32;
+                        let id = mundo.obtener(posicion[0], posicion[1], posicion[2]);
+
+                        let mut vecino = posicion;
+                        vecino[eje] += paso;
+                        let id_vecino = mundo.obtener(vecino[0], vecino[1], vecino[2]);
+
+                        mascara[j * ancho + i] = (id != AIRE && cara_visible(mundo, id, id_vecino))
+                            .then(|| (id, atributo(posicion, eje, positiva)));
+                    }
+                }
+
+                // Recorrer la máscara extendiendo cada cara en u y luego en v
+                let plano = minimo[eje] + capa + if positiva { 1 } else { 0 };
+                for j in 0..alto {
+                    let mut i = 0;
+                    while i < ancho {
+                        let celda = match mascara[j * ancho + i] {
+                            Some(celda) => celda,
+                            None => {
+                                i += 1;
+                                continue;
+                            }
+                        };
+
+                        let mut w = 1;
+                        let mut h = 1;
+                        if voraz {
+                            while i + w < ancho && mascara[j * ancho + i + w] == Some(celda) {
+                                w += 1;
+                            }
+                            while j + h < alto
+                                && (i..i + w).all(|k| mascara[(j + h) * ancho + k] == Some(celda))
+                            {
+                                h += 1;
+                            }
+                        }
+                        for fila in j..j + h {
+                            mascara[fila * ancho + i..fila * ancho + i + w].fill(None);
+                        }
+
+                        let mut origen = [0; 3];
+                        origen[eje] = plano;
+                        origen[u] = minimo[u] + i as i32;
+                        origen[v] = minimo[v] + j as i32;
+                        malla.caras.push(Cara {
+                            eje,
+                            positiva,
+                            origen,
+                            ancho: w as i32,
+                            alto: h as i32,
+                            id: celda.0,
+                            atributo: celda.1,
+                        });
+                        i += w;
+                    }
+                }
+            }
+        }
+    }
+
+    malla
+}
@@ -1,4 +1,41 @@
-use nalgebra::Vector3;
+use nalgebra::{Vector3, Point3};
+
+/// Máscara de recorte alfa de 16x16 téxeles por cara, con un bit a 1 por téxel opaco.
+/// El ray tracer no carga las texturas, así que los materiales con huecos llevan la suya.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MascaraRecorte {
+    pub filas: [u16; 16],              // El bit más alto de cada fila es el primer téxel
+}
+
+impl MascaraRecorte {
+    /// Hojas de árbol: copa tupida con un tercio de huecos
+    pub const HOJAS: MascaraRecorte = MascaraRecorte {
+        filas: [
+            0b1111_1101_1101_0110,
+            0b1111_0010_0001_1111,
+            0b1110_1111_1000_1111,
+            0b1100_1111_0110_0111,
+            0b0000_1011_0101_0100,
+            0b1111_0111_0100_1100,
+            0b0101_1111_1100_1100,
+            0b1101_1110_0101_0110,
+            0b0100_1110_1000_1001,
+            0b1011_1111_0001_1101,
+            0b1111_0111_1111_1111,
+            0b1111_1111_0111_1111,
+            0b1101_0110_1111_0011,
+            0b0011_1111_0111_1111,
+            0b0111_1100_1001_1111,
+            0b1100_1011_1100_1010,
+        ],
+    };
+
+    /// El téxel en (u, v) es opaco; la máscara se repite en cada bloque
+    pub fn es_opaca(&self, u: f64, v: f64) -> bool {
+        let texel = |c: f64| ((c.rem_euclid(1.0) * 16.0) as usize).min(15);
+        (self.filas[texel(v)] >> (15 - texel(u))) & 1 == 1
+    }
+}
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -11,6 +48,8 @@ pub struct Material {
     pub rugosidad: f64,                // Para efectos de especular
     #[allow(dead_code)]
     pub brillo: f64,                   // Intensidad del brillo especular
+    pub emision: Vector3<f64>,         // Radiancia emitida (cero = no emite luz)
+    pub recorte_alfa: Option<MascaraRecorte>, // Huecos de la textura (hojas): dejan pasar la luz
     pub textura_nombre: Option<String>, // Nombre de la textura a usar
 }
 
@@ -25,6 +64,8 @@ impl Material {
             indice_refraccion,
             rugosidad,
             brillo,
+            emision: Vector3::zeros(),
+            recorte_alfa: None,
             textura_nombre: None,
         }
     }
@@ -39,10 +80,62 @@ impl Material {
             indice_refraccion,
             rugosidad,
             brillo,
+            emision: Vector3::zeros(),
+            recorte_alfa: None,
             textura_nombre: Some(textura),
         }
     }
     
+    pub fn emisivo(albedo: Vector3<f64>, emision: Vector3<f64>) -> Self {
+        Self {
+            emision,
+            ..Self::nuevo(albedo, 0.0, 0.0, 1.0, 0.9, 0.0)
+        }
+    }
+    
+    /// Indica si el material actúa como fuente de luz
+    pub fn es_emisivo(&self) -> bool {
+        self.emision.max() > 0.0
+    }
+    
+    /// Identificador estable del material (hash de sus propiedades) para las pasadas de ID
+    pub fn identificador(&self) -> u32 {
+        let mut bytes = Vec::new();
+        for valor in [self.albedo.x, self.albedo.y, self.albedo.z, self.reflectividad, self.transparencia,
+                      self.indice_refraccion, self.rugosidad, self.brillo,
+                      self.emision.x, self.emision.y, self.emision.z] {
+            bytes.extend_from_slice(&valor.to_bits().to_le_bytes());
+        }
+        for fila in self.recorte_alfa.map_or([0; 16], |mascara| mascara.filas) {
+            bytes.extend_from_slice(&fila.to_le_bytes());
+        }
+        bytes.extend_from_slice(self.textura_nombre.as_deref().unwrap_or("").as_bytes());
+        hash_fnv(&bytes)
+    }
+    
+    /// Identificador de la textura (0 si el material no tiene)
+    pub fn identificador_textura(&self) -> u32 {
+        match &self.textura_nombre {
+            Some(nombre) => hash_fnv(nombre.as_bytes()).max(1),
+            None => 0,
+        }
+    }
+    
+    /// Prueba de recorte alfa: falso si el punto cae en un hueco de la máscara del
+    /// material, tomando como UV las coordenadas en el plano de la cara golpeada
+    pub fn es_opaco_en(&self, punto: &Point3<f64>, normal: &Vector3<f64>) -> bool {
+        let mascara = match &self.recorte_alfa {
+            Some(mascara) => mascara,
+            None => return true,
+        };
+        let (u, v) = match normal.iamax() {
+            0 => (punto.z, punto.y),
+            1 => (punto.x, punto.z),
+            _ => (punto.x, punto.y),
+        };
+        mascara.es_opaca(u, v)
+    }
+    
     // Materiales estilo Minecraft con colores reconocibles
     
     /// Bloque de piedra - gris clásico de Minecraft
@@ -119,13 +212,24 @@ impl Material {
     
     /// Lava - naranja-rojo intenso
     pub fn lava() -> Self {
-        Self::nuevo(
-            Vector3::new(1.0, 0.4, 0.0),  // Naranja-rojo lava
-            0.0,                           // No reflectante
-            0.0,                           // Opaco
-            1.0,                           // Sin refracción
-            0.8,                           // Rugoso
-            1.0                            // Muy brillante (emisivo)
+        Self {
+            emision: Vector3::new(4.0, 1.4, 0.2),  // Brillo propio naranja
+            ..Self::nuevo(
+                Vector3::new(1.0, 0.4, 0.0),  // Naranja-rojo lava
+                0.0,                           // No reflectante
+                0.0,                           // Opaco
+                1.0,                           // Sin refracción
+                0.8,                           // Rugoso
+                0.2                            // Poco brillo especular
+            )
+        }
+    }
+    
+    /// Piedra luminosa (glowstone) - amarillo cálido que ilumina
+    pub fn piedra_luminosa() -> Self {
+        Self::emisivo(
+            Vector3::new(0.9, 0.75, 0.45),  // Amarillo arena claro
+            Vector3::new(3.5, 2.8, 1.6)     // Luz cálida
         )
     }
     
@@ -215,6 +319,14 @@ impl Material {
         )
     }
     
+    /// Nieve de las cumbres
+    pub fn nieve() -> Self {
+        Self::nuevo(
+            Vector3::new(0.95, 0.97, 1.0), // Blanco azulado
+            0.05, 0.0, 1.0, 0.6, 0.1
+        )
+    }
+    
     /// Hojas claras
     pub fn hojas_claras() -> Self {
         Self::nuevo(
@@ -369,7 +481,7 @@ impl Material {
     pub fn water_texturizado() -> Self {
         Self::con_textura(
             Vector3::new(0.1, 0.3, 0.8),  // Azul agua INTENSO
-            0.0, 0.0, 1.0, 0.9, 0.05,
+            0.0, 0.6, 1.0, 0.9, 0.05,     // Deja pasar luz: los lagos dan sombra teñida, no negra
             "water".to_string()
         )
     }
@@ -384,11 +496,14 @@ impl Material {
     }
     
     pub fn oak_leaves_texturizado() -> Self {
-        Self::con_textura(
-            Vector3::new(0.0, 0.7, 0.0),  // Verde hojas SÚPER INTENSO
-            0.0, 0.0, 1.0, 0.85, 0.1,
-            "oak_leaves".to_string()
-        )
+        Self {
+            recorte_alfa: Some(MascaraRecorte::HOJAS),
+            ..Self::con_textura(
+                Vector3::new(0.0, 0.7, 0.0),  // Verde hojas SÚPER INTENSO
+                0.0, 0.0, 1.0, 0.85, 0.1,
+                "oak_leaves".to_string()
+            )
+        }
     }
     
     // ÁRBOLES - ABEDUL
@@ -401,11 +516,14 @@ impl Material {
     }
     
     pub fn birch_leaves_texturizado() -> Self {
-        Self::con_textura(
-            Vector3::new(0.2, 0.8, 0.3),  // Verde hojas abedul
-            0.0, 0.0, 1.0, 0.7, 0.2,
-            "birch_leaves".to_string()
-        )
+        Self {
+            recorte_alfa: Some(MascaraRecorte::HOJAS),
+            ..Self::con_textura(
+                Vector3::new(0.2, 0.8, 0.3),  // Verde hojas abedul
+                0.0, 0.0, 1.0, 0.7, 0.2,
+                "birch_leaves".to_string()
+            )
+        }
     }
     
     // ÁRBOLES - JUNGLA
@@ -418,17 +536,80 @@ impl Material {
     }
     
     pub fn jungle_leaves_texturizado() -> Self {
+        Self {
+            recorte_alfa: Some(MascaraRecorte::HOJAS),
+            ..Self::con_textura(
+                Vector3::new(0.0, 0.9, 0.1),  // Verde hojas jungla INTENSO
+                0.0, 0.0, 1.0, 0.7, 0.2,
+                "jungle_leaves".to_string()
+            )
+        }
+    }
+    
+    // ÁRBOLES - CEREZO
+    pub fn cherry_log_texturizado() -> Self {
+        Self::con_textura(
+            Vector3::new(0.35, 0.18, 0.2),  // Corteza granate oscura
+            0.0, 0.0, 1.0, 0.85, 0.05,
+            "cherry_log".to_string()
+        )
+    }
+    
+    pub fn cherry_leaves_texturizado() -> Self {
+        Self {
+            recorte_alfa: Some(MascaraRecorte::HOJAS),
+            ..Self::con_textura(
+                Vector3::new(1.0, 0.68, 0.8),  // Flor rosa
+                0.0, 0.0, 1.0, 0.7, 0.2,
+                "cherry_leaves".to_string()
+            )
+        }
+    }
+    
+    // ÁRBOLES - ABETO
+    pub fn spruce_log_texturizado() -> Self {
+        Self::con_textura(
+            Vector3::new(0.3, 0.2, 0.12),  // Café muy oscuro
+            0.0, 0.0, 1.0, 0.9, 0.05,
+            "spruce_log".to_string()
+        )
+    }
+    
+    pub fn spruce_leaves_texturizado() -> Self {
+        Self {
+            recorte_alfa: Some(MascaraRecorte::HOJAS),
+            ..Self::con_textura(
+                Vector3::new(0.15, 0.38, 0.22),  // Verde azulado de conífera
+                0.0, 0.0, 1.0, 0.8, 0.1,
+                "spruce_leaves".to_string()
+            )
+        }
+    }
+    
+    // ÁRBOLES - ACACIA
+    pub fn acacia_log_texturizado() -> Self {
         Self::con_textura(
-            Vector3::new(0.0, 0.9, 0.1),  // Verde hojas jungla INTENSO
-            0.0, 0.0, 1.0, 0.7, 0.2,
-            "jungle_leaves".to_string()
+            Vector3::new(0.45, 0.42, 0.38),  // Corteza gris
+            0.0, 0.0, 1.0, 0.9, 0.05,
+            "acacia_log".to_string()
         )
     }
     
+    pub fn acacia_leaves_texturizado() -> Self {
+        Self {
+            recorte_alfa: Some(MascaraRecorte::HOJAS),
+            ..Self::con_textura(
+                Vector3::new(0.42, 0.58, 0.12),  // Verde oliva de sabana
+                0.0, 0.0, 1.0, 0.8, 0.1,
+                "acacia_leaves".to_string()
+            )
+        }
+    }
+    
     // MINERALES
     pub fn coal_ore_texturizado() -> Self {
         Self::con_textura(
-            Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(0.22, 0.22, 0.24),  // Piedra salpicada de carbón
             0.05, 0.0, 1.0, 0.9, 0.1,
             "coal_ore".to_string()
         )
@@ -436,9 +617,14 @@ impl Material {
     
     pub fn iron_ore_texturizado() -> Self {
         Self::con_textura(
-            Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(0.78, 0.6, 0.48),   // Vetas color óxido
             0.2, 0.0, 1.0, 0.8, 0.3,
             "iron_ore".to_string()
         )
     }
-}
\ No newline at end of file
+}
+
+// FNV-1a de 32 bits
+fn hash_fnv(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5u32, |h, &b| (h ^ b as u32).wrapping_mul(0x0100_0193))
+}
@@ -0,0 +1,177 @@
+use rand::Rng;
+
+/// Cómo se reparten las muestras dentro de cada píxel
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PatronMuestreo {
+    Aleatorio,
+    /// Rejilla de estratos con una muestra desplazada al azar en cada uno
+    Estratificado,
+    /// Secuencia de Halton en bases 2 y 3
+    Halton,
+    /// Secuencia de Sobol (0,2) en dos dimensiones
+    Sobol,
+}
+
+/// Filtro con el que cada muestra se reparte entre los píxeles vecinos
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FiltroReconstruccion {
+    Caja,
+    Tienda { radio: f64 },
+    Gaussiano { radio: f64, alfa: f64 },
+    Mitchell { radio: f64, b: f64, c: f64 },
+}
+
+/// Patrón, filtro y número de muestras por píxel en cada pasada
+#[derive(Clone, Copy, Debug)]
+pub struct Muestreador {
+    pub patron: PatronMuestreo,
+    pub filtro: FiltroReconstruccion,
+    pub muestras_por_pixel: u32,
+}
+
+impl Default for Muestreador {
+    fn default() -> Self {
+        Self {
+            patron: PatronMuestreo::Estratificado,
+            filtro: FiltroReconstruccion::mitchell(),
+            muestras_por_pixel: 1,
+        }
+    }
+}
+
+impl Muestreador {
+    pub fn nuevo(patron: PatronMuestreo, filtro: FiltroReconstruccion, muestras_por_pixel: u32) -> Self {
+        Self {
+            patron,
+            filtro,
+            muestras_por_pixel: muestras_por_pixel.max(1),
+        }
+    }
+
+    /// Desplazamiento (u, v) en [0, 1)² de la muestra `indice` dentro del píxel (x, y).
+    /// `total` es el número de muestras que se esperan en el píxel (para estratificar).
+    pub fn muestra<R: Rng>(&self, x: usize, y: usize, indice: u32, total: u32, rng: &mut R) -> (f64, f64) {
+        match self.patron {
+            PatronMuestreo::Aleatorio => (rng.gen(), rng.gen()),
+            PatronMuestreo::Estratificado => {
+                let lado = (total.max(1) as f64).sqrt().floor().max(1.0) as u32;
+                let estrato = indice % (lado * lado);
+                let (i, j) = (estrato % lado, estrato / lado);
+                (
+                    (i as f64 + rng.gen::<f64>()) / lado as f64,
+                    (j as f64 + rng.gen::<f64>()) / lado as f64,
+                )
+            }
+            PatronMuestreo::Halton => {
+                // Rotación de Cranley-Patterson fija por píxel para no repetir el patrón
+                let (du, dv) = desplazamiento_pixel(x, y);
+                (
+                    (inverso_radical(indice, 2) + du).fract(),
+                    (inverso_radical(indice, 3) + dv).fract(),
+                )
+            }
+            PatronMuestreo::Sobol => {
+                let (du, dv) = desplazamiento_pixel(x, y);
+                let (u, v) = sobol_2d(indice);
+                ((u + du).fract(), (v + dv).fract())
+            }
+        }
+    }
+}
+
+impl FiltroReconstruccion {
+    pub fn tienda() -> Self {
+        Self::Tienda { radio: 1.0 }
+    }
+
+    pub fn gaussiano() -> Self {
+        Self::Gaussiano { radio: 1.5, alfa: 2.0 }
+    }
+
+    /// Mitchell-Netravali con B = C = 1/3
+    pub fn mitchell() -> Self {
+        Self::Mitchell { radio: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 }
+    }
+
+    /// Alcance del filtro en píxeles
+    pub fn radio(&self) -> f64 {
+        match *self {
+            Self::Caja => 0.5,
+            Self::Tienda { radio } | Self::Gaussiano { radio, .. } | Self::Mitchell { radio, .. } => radio,
+        }
+    }
+
+    /// Peso de una muestra a (dx, dy) píxeles del centro; separable en x e y
+    pub fn evaluar(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluar_1d(dx) * self.evaluar_1d(dy)
+    }
+
+    fn evaluar_1d(&self, d: f64) -> f64 {
+        let d = d.abs();
+        match *self {
+            Self::Caja => if d <= 0.5 { 1.0 } else { 0.0 },
+            Self::Tienda { radio } => (1.0 - d / radio).max(0.0),
+            Self::Gaussiano { radio, alfa } => {
+                ((-alfa * d * d).exp() - (-alfa * radio * radio).exp()).max(0.0)
+            }
+            Self::Mitchell { radio, b, c } => {
+                // El núcleo original abarca [-2, 2]
+                let x = 2.0 * d / radio;
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)) / 6.0
+                } else if x < 2.0 {
+                    ((-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)) / 6.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+// Inverso radical de `indice` en la base dada (van der Corput)
+fn inverso_radical(mut indice: u32, base: u32) -> f64 {
+    let inversa = 1.0 / base as f64;
+    let mut factor = inversa;
+    let mut resultado = 0.0;
+    while indice > 0 {
+        resultado += (indice % base) as f64 * factor;
+        indice /= base;
+        factor *= inversa;
+    }
+    resultado
+}
+
+// Primeras dos dimensiones de Sobol: inverso de bits y matriz de Pascal binaria
+fn sobol_2d(indice: u32) -> (f64, f64) {
+    let escala = 1.0 / 4294967296.0;
+    let u = indice.reverse_bits();
+
+    let mut v = 0u32;
+    let mut direccion = 1u32 << 31;
+    let mut resto = indice;
+    while resto > 0 {
+        if resto & 1 == 1 {
+            v ^= direccion;
+        }
+        direccion ^= direccion >> 1;
+        resto >>= 1;
+    }
+
+    (u as f64 * escala, v as f64 * escala)
+}
+
+// Desplazamiento pseudoaleatorio y estable para cada píxel
+fn desplazamiento_pixel(x: usize, y: usize) -> (f64, f64) {
+    let mut h = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    let escala = 1.0 / 4294967296.0;
+    ((h >> 32) as f64 * escala, (h & 0xFFFF_FFFF) as f64 * escala)
+}
@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::materiales::Material;
+
+/// Identificador compacto de tipo de bloque (índice en la paleta)
+pub type IdBloque = u16;
+
+/// Posición entera de un bloque (x, y, z)
+pub type Posicion = (i32, i32, i32);
+
+/// Lado horizontal de un chunk en bloques
+pub const TAMANO_CHUNK: i32 = 16;
+
+// Identificadores de `Paleta::minecraft` (en el orden en que se registran)
+pub const AIRE: IdBloque = 0;
+pub const PASTO: IdBloque = 1;
+pub const TIERRA: IdBloque = 2;
+pub const PIEDRA: IdBloque = 3;
+#[allow(dead_code)]    // Registrado en la paleta aunque ninguna escena lo use
+pub const ADOQUIN: IdBloque = 4;
+pub const GRAVA: IdBloque = 5;
+pub const ARENA: IdBloque = 6;
+pub const AGUA: IdBloque = 7;
+pub const LAVA: IdBloque = 8;
+pub const PIEDRA_LUMINOSA: IdBloque = 9;
+pub const TRONCO_ROBLE: IdBloque = 10;
+pub const HOJAS_ROBLE: IdBloque = 11;
+pub const TRONCO_ABEDUL: IdBloque = 12;
+pub const HOJAS_ABEDUL: IdBloque = 13;
+pub const TRONCO_JUNGLA: IdBloque = 14;
+pub const HOJAS_JUNGLA: IdBloque = 15;
+pub const CARBON: IdBloque = 16;
+pub const HIERRO: IdBloque = 17;
+pub const NIEVE: IdBloque = 18;
+pub const TRONCO_CEREZO: IdBloque = 19;
+pub const HOJAS_CEREZO: IdBloque = 20;
+pub const TRONCO_ABETO: IdBloque = 21;
+pub const HOJAS_ABETO: IdBloque = 22;
+pub const TRONCO_ACACIA: IdBloque = 23;
+pub const HOJAS_ACACIA: IdBloque = 24;
+
+/// Materiales de los tipos de bloque. El índice 0 es siempre el aire.
+#[derive(Clone)]
+pub struct Paleta {
+    materiales: Vec<Option<Material>>,
+    nombres: HashMap<String, IdBloque>,
+}
+
+impl Paleta {
+    pub fn nueva() -> Self {
+        let mut nombres = HashMap::new();
+        nombres.insert("aire".to_string(), AIRE);
+        Self {
+            materiales: vec![None],
+            nombres,
+        }
+    }
+
+    /// Bloques de las escenas Minecraft; sus identificadores son las constantes del módulo
+    pub fn minecraft() -> Self {
+        let mut paleta = Self::nueva();
+        for (nombre, material) in [
+            ("pasto", Material::grass_top_texturizado()),
+            ("tierra", Material::dirt_texturizado()),
+            ("piedra", Material::stone_texturizado()),
+            ("adoquin", Material::cobblestone_texturizado()),
+            ("grava", Material::gravel_texturizado()),
+            ("arena", Material::arena()),
+            ("agua", Material::water_texturizado()),
+            ("lava", Material::lava()),
+            ("piedra_luminosa", Material::piedra_luminosa()),
+            ("tronco_roble", Material::oak_log_texturizado()),
+            ("hojas_roble", Material::oak_leaves_texturizado()),
+            ("tronco_abedul", Material::birch_log_texturizado()),
+            ("hojas_abedul", Material::birch_leaves_texturizado()),
+            ("tronco_jungla", Material::jungle_log_texturizado()),
+            ("hojas_jungla", Material::jungle_leaves_texturizado()),
+            ("carbon", Material::coal_ore_texturizado()),
+            ("hierro", Material::iron_ore_texturizado()),
+            ("nieve", Material::nieve()),
+            ("tronco_cerezo", Material::cherry_log_texturizado()),
+            ("hojas_cerezo", Material::cherry_leaves_texturizado()),
+            ("tronco_abeto", Material::spruce_log_texturizado()),
+            ("hojas_abeto", Material::spruce_leaves_texturizado()),
+            ("tronco_acacia", Material::acacia_log_texturizado()),
+            ("hojas_acacia", Material::acacia_leaves_texturizado()),
+        ] {
+            paleta.registrar(nombre, material);
+        }
+        paleta
+    }
+
+    /// Agrega un tipo de bloque; si el nombre ya existe devuelve su identificador
+    pub fn registrar(&mut self, nombre: &str, material: Material) -> IdBloque {
+        if let Some(&id) = self.nombres.get(nombre) {
+            return id;
+        }
+        let id = IdBloque::try_from(self.materiales.len()).expect("Paleta llena");
+        self.materiales.push(Some(material));
+        self.nombres.insert(nombre.to_string(), id);
+        id
+    }
+
+    /// Material del bloque (`None` para el aire o identificadores desconocidos)
+    pub fn material(&self, id: IdBloque) -> Option<&Material> {
+        self.materiales.get(id as usize).and_then(|m| m.as_ref())
+    }
+}
+
+/// Columna de 16×16×`altura` bloques
+#[derive(Clone)]
+pub struct Chunk {
+    bloques: Vec<IdBloque>,
+    ocupados: usize,               // Bloques que no son aire
+}
+
+impl Chunk {
+    fn nuevo(altura: usize) -> Self {
+        Self {
+            bloques: vec![AIRE; (TAMANO_CHUNK * TAMANO_CHUNK) as usize * altura],
+            ocupados: 0,
+        }
+    }
+
+    // Coordenadas locales: x, z en 0..16
+    fn indice(x: i32, y: i32, z: i32) -> usize {
+        ((y * TAMANO_CHUNK + z) * TAMANO_CHUNK + x) as usize
+    }
+
+    pub fn esta_vacio(&self) -> bool {
+        self.ocupados == 0
+    }
+}
+
+/// Mundo de vóxeles dividido en chunks. Las coordenadas enteras (x, y, z) son la
+/// esquina mínima del bloque, que ocupa hasta (x+1, y+1, z+1); y va de 0 a `altura`.
+#[derive(Clone)]
+pub struct Mundo {
+    pub altura: usize,
+    pub paleta: Paleta,
+    chunks: BTreeMap<(i32, i32), Chunk>,   // Ordenados para que el recorrido sea estable
+}
+
+impl Mundo {
+    /// Mundo vacío con la paleta Minecraft
+    pub fn nuevo(altura: usize) -> Self {
+        Self::con_paleta(altura, Paleta::minecraft())
+    }
+
+    pub fn con_paleta(altura: usize, paleta: Paleta) -> Self {
+        Self {
+            altura,
+            paleta,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    // Chunk que contiene la columna (x, z) y la posición dentro de él
+    fn ubicar(x: i32, z: i32) -> ((i32, i32), i32, i32) {
+        (
+            (x.div_euclid(TAMANO_CHUNK), z.div_euclid(TAMANO_CHUNK)),
+            x.rem_euclid(TAMANO_CHUNK),
+            z.rem_euclid(TAMANO_CHUNK),
+        )
+    }
+
+    /// Coordenada (x, z) del chunk que contiene la columna
+    pub fn chunk_de(x: i32, z: i32) -> (i32, i32) {
+        Self::ubicar(x, z).0
+    }
+
+    pub fn dentro_de_altura(&self, y: i32) -> bool {
+        y >= 0 && (y as usize) < self.altura
+    }
+
+    /// Bloque en la posición; fuera del mundo todo es aire
+    pub fn obtener(&self, x: i32, y: i32, z: i32) -> IdBloque {
+        if !self.dentro_de_altura(y) {
+            return AIRE;
+        }
+        let (clave, lx, lz) = Self::ubicar(x, z);
+        self.chunks
+            .get(&clave)
+            .map_or(AIRE, |chunk| chunk.bloques[Chunk::indice(lx, y, lz)])
+    }
+
+    /// Reemplaza el bloque; devuelve falso si la altura está fuera del mundo
+    pub fn colocar(&mut self, x: i32, y: i32, z: i32, id: IdBloque) -> bool {
+        if !self.dentro_de_altura(y) {
+            return false;
+        }
+        let (clave, lx, lz) = Self::ubicar(x, z);
+        if id == AIRE && !self.chunks.contains_key(&clave) {
+            return true;
+        }
+        let altura = self.altura;
+        let chunk = self.chunks.entry(clave).or_insert_with(|| Chunk::nuevo(altura));
+        let celda = &mut chunk.bloques[Chunk::indice(lx, y, lz)];
+        match (*celda == AIRE, id == AIRE) {
+            (true, false) => chunk.ocupados += 1,
+            (false, true) => chunk.ocupados -= 1,
+            _ => {}
+        }
+        *celda = id;
+        true
+    }
+
+    /// Como `colocar`, pero solo si la posición está vacía
+    pub fn colocar_si_aire(&mut self, x: i32, y: i32, z: i32, id: IdBloque) -> bool {
+        self.es_aire(x, y, z) && self.colocar(x, y, z, id)
+    }
+
+    /// Llena la caja de bloques entre `minimo` y `maximo` (ambos incluidos)
+    pub fn rellenar(&mut self, minimo: Posicion, maximo: Posicion, id: IdBloque) {
+        for x in minimo.0..=maximo.0 {
+            for y in minimo.1..=maximo.1 {
+                for z in minimo.2..=maximo.2 {
+                    self.colocar(x, y, z, id);
+                }
+            }
+        }
+    }
+
+    pub fn es_aire(&self, x: i32, y: i32, z: i32) -> bool {
+        self.obtener(x, y, z) == AIRE
+    }
+
+    /// Altura del bloque sólido más alto de la columna
+    pub fn superficie(&self, x: i32, z: i32) -> Option<i32> {
+        (0..self.altura as i32).rev().find(|&y| !self.es_aire(x, y, z))
+    }
+
+    /// Chunks con su coordenada de chunk (x, z)
+    pub fn chunks(&self) -> impl Iterator<Item = ((i32, i32), &Chunk)> {
+        self.chunks.iter().map(|(clave, chunk)| (*clave, chunk))
+    }
+
+    /// Todos los bloques que no son aire con su posición
+    pub fn bloques(&self) -> impl Iterator<Item = (Posicion, IdBloque)> + '_ {
+        self.chunks.iter().filter(|(_, chunk)| !chunk.esta_vacio()).flat_map(|(&(cx, cz), chunk)| {
+            chunk.bloques.iter().enumerate().filter(|(_, &id)| id != AIRE).map(move |(i, &id)| {
+                let i = i as i32;
+                let x = cx * TAMANO_CHUNK + i % TAMANO_CHUNK;
+                let z = cz * TAMANO_CHUNK + (i / TAMANO_CHUNK) % TAMANO_CHUNK;
+                let y = i / (TAMANO_CHUNK * TAMANO_CHUNK);
+                ((x, y, z), id)
+            })
+        })
+    }
+
+    pub fn numero_bloques(&self) -> usize {
+        self.chunks.values().map(|chunk| chunk.ocupados).sum()
+    }
+
+    /// Copia sin los bloques cuya coordenada en `eje` (0 = x, 1 = y, 2 = z) es `desde`
+    /// o mayor: un corte que deja ver el interior del terreno
+    pub fn cortado(&self, eje: usize, desde: i32) -> Mundo {
+        let mut mundo = self.clone();
+        let quitar: Vec<Posicion> = self
+            .bloques()
+            .map(|(posicion, _)| posicion)
+            .filter(|&(x, y, z)| [x, y, z][eje] >= desde)
+            .collect();
+        for (x, y, z) in quitar {
+            mundo.colocar(x, y, z, AIRE);
+        }
+        mundo
+    }
+}
@@ -0,0 +1,199 @@
+use nalgebra::Vector3;
+use crate::camara::Camara;
+use crate::escena::Escena;
+use crate::geometria::Rayo;
+use crate::renderizador::{para_cada_pixel, radiancia_fondo, rayo_imagen};
+use crate::tonos::{self, Revelado};
+use std::path::Path;
+
+const EPSILON: f64 = 1e-4;
+const MUESTRAS_SOMBRA: u32 = 4;
+const HUECOS_MAXIMOS: u32 = 16;
+
+/// Datos del primer impacto opaco visto por el centro de un píxel
+#[derive(Clone)]
+pub struct PixelAuxiliar {
+    pub profundidad: f64,          // `Interseccion::t` (infinito si el rayo no golpea nada)
+    pub normal: Vector3<f64>,      // Normal del mundo (cero en el fondo)
+    pub albedo: Vector3<f64>,      // Color sin iluminar (el cielo en el fondo)
+    pub id_material: u32,          // 0 = fondo
+    pub id_textura: u32,           // 0 = sin textura
+    pub visibilidad: f64,          // Fracción de luz directa que no queda en sombra
+    pub id_objeto: u32,            // Índice en `Escena::objetos` + 1; 0 = fondo
+}
+
+impl Default for PixelAuxiliar {
+    fn default() -> Self {
+        Self {
+            profundidad: f64::INFINITY,
+            normal: Vector3::zeros(),
+            albedo: Vector3::zeros(),
+            id_material: 0,
+            id_textura: 0,
+            visibilidad: 1.0,
+            id_objeto: 0,
+        }
+    }
+}
+
+/// Búferes auxiliares (AOV) para composición y depuración
+pub struct PasadasAuxiliares {
+    pub ancho: usize,
+    pub alto: usize,
+    pub pixeles: Vec<PixelAuxiliar>,
+}
+
+// Luz directa que llega al punto y la parte que no bloquea la escena
+fn visibilidad_sombra<R: rand::Rng>(escena: &Escena, punto: &nalgebra::Point3<f64>,
+                                    normal: &Vector3<f64>, rng: &mut R) -> f64 {
+    let luminancia = |c: &Vector3<f64>| 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z;
+    let origen = punto + normal * EPSILON;
+    let (mut total, mut visible) = (0.0, 0.0);
+
+    for luz in escena.luces.iter().filter(|luz| !luz.es_cielo()) {
+        for _ in 0..MUESTRAS_SOMBRA {
+            let muestra = match luz.muestrear(&origen, normal, rng) {
+                Some(muestra) => muestra,
+                None => continue,
+            };
+            let coseno = muestra.direccion.dot(normal);
+            if coseno <= 0.0 || muestra.pdf <= 0.0 {
+                continue;
+            }
+
+            let aporte = luminancia(&muestra.radiancia) * coseno / muestra.pdf;
+            let sombra = Rayo::new(origen, muestra.direccion);
+            let transmitancia = escena.transmitancia(&sombra, muestra.distancia - EPSILON);
+            total += aporte;
+            visible += aporte * luminancia(&transmitancia);
+        }
+    }
+
+    if total > 0.0 { visible / total } else { 1.0 }
+}
+
+// Color arbitrario pero estable para visualizar un identificador
+fn color_identificador(id: u32) -> Vector3<f64> {
+    if id == 0 {
+        return Vector3::zeros();
+    }
+    let h = id.wrapping_mul(0x9E37_79B1).rotate_left(13).wrapping_mul(0x85EB_CA6B);
+    Vector3::new((h >> 24) as f64, ((h >> 16) & 0xFF) as f64, ((h >> 8) & 0xFF) as f64) / 255.0
+}
+
+// Identificador exacto en flotantes: 16 bits altos en R y 16 bajos en G
+fn identificador_exr(id: u32) -> Vector3<f64> {
+    Vector3::new((id >> 16) as f64, (id & 0xFFFF) as f64, 0.0)
+}
+
+impl PasadasAuxiliares {
+    /// Lanza un rayo por el centro de cada píxel y guarda su primer impacto opaco
+    pub fn calcular(escena: &Escena, camara: &Camara, ancho: usize, alto: usize) -> Self {
+        let mut pixeles = vec![PixelAuxiliar::default(); ancho * alto];
+
+        para_cada_pixel(&mut pixeles, ancho, alto, |x, y, pixel, rng| {
+            let mut rayo = rayo_imagen(camara, x as f64 + 0.5, y as f64 + 0.5, ancho, alto);
+            let mut recorrido = 0.0;
+
+            for _ in 0..HUECOS_MAXIMOS {
+                let (indice, interseccion) = match escena.intersectar_con_indice(&rayo) {
+                    Some(impacto) => impacto,
+                    None => {
+                        pixel.albedo = radiancia_fondo(escena, &rayo).map(|c| c.clamp(0.0, 1.0));
+                        return;
+                    }
+                };
+                let material = &interseccion.material;
+
+                // Los huecos de las hojas dejan ver lo que hay detrás
+                if !material.es_opaco_en(&interseccion.punto, &interseccion.normal) {
+                    recorrido += interseccion.t + EPSILON;
+                    rayo = Rayo::new(interseccion.punto + rayo.direccion * EPSILON, rayo.direccion);
+                    continue;
+                }
+
+                let mut normal = interseccion.normal;
+                if normal.dot(&rayo.direccion) > 0.0 {
+                    normal = -normal;
+                }
+
+                *pixel = PixelAuxiliar {
+                    profundidad: recorrido + interseccion.t,
+                    normal,
+                    albedo: material.albedo,
+                    id_material: material.identificador(),
+                    id_textura: material.identificador_textura(),
+                    visibilidad: visibilidad_sombra(escena, &interseccion.punto, &normal, rng),
+                    id_objeto: indice as u32 + 1,
+                };
+                return;
+            }
+        });
+
+        Self { ancho, alto, pixeles }
+    }
+
+    pub fn normales(&self) -> Vec<Vector3<f64>> {
+        self.pixeles.iter().map(|p| p.normal).collect()
+    }
+
+    pub fn albedos(&self) -> Vec<Vector3<f64>> {
+        self.pixeles.iter().map(|p| p.albedo).collect()
+    }
+
+    /// Escribe cada pasada como `<nombre>_<pasada>.png` en `directorio` y, con `exr`,
+    /// también los valores sin procesar en `<nombre>_<pasada>.exr`. En los EXR de
+    /// identificadores R guarda los 16 bits altos y G los 16 bajos.
+    pub fn guardar(&self, directorio: &Path, nombre: &str, exr: bool) -> Result<(), String> {
+        let (ancho, alto) = (self.ancho, self.alto);
+        let ruta = |pasada: &str, extension: &str| directorio.join(format!("{}_{}.{}", nombre, pasada, extension));
+        let lineal = Revelado::lineal();
+
+        // Profundidad: cerca = blanco, lejos y fondo = negro
+        let maxima = self
+            .pixeles
+            .iter()
+            .map(|p| p.profundidad)
+            .filter(|t| t.is_finite())
+            .fold(0.0, f64::max)
+            .max(1e-9);
+        let profundidad_visible: Vec<_> = self
+            .pixeles
+            .iter()
+            .map(|p| if p.profundidad.is_finite() { 1.0 - p.profundidad / maxima } else { 0.0 })
+            .map(|v| Vector3::new(v, v, v))
+            .collect();
+        tonos::guardar_png_datos(&ruta("profundidad", "png"), ancho, alto, &profundidad_visible)?;
+
+        let normales_visibles: Vec<_> = self.pixeles.iter().map(|p| p.normal * 0.5 + Vector3::repeat(0.5)).collect();
+        tonos::guardar_png_datos(&ruta("normal", "png"), ancho, alto, &normales_visibles)?;
+        tonos::guardar_imagen(&ruta("albedo", "png"), ancho, alto, &self.albedos(), &lineal)?;
+
+        let sombra: Vec<_> = self.pixeles.iter().map(|p| Vector3::repeat(p.visibilidad)).collect();
+        tonos::guardar_png_datos(&ruta("sombra", "png"), ancho, alto, &sombra)?;
+
+        let identificadores = [
+            ("material", self.pixeles.iter().map(|p| p.id_material).collect::<Vec<_>>()),
+            ("textura", self.pixeles.iter().map(|p| p.id_textura).collect()),
+            ("objeto", self.pixeles.iter().map(|p| p.id_objeto).collect()),
+        ];
+        for (pasada, ids) in &identificadores {
+            let colores: Vec<_> = ids.iter().map(|id| color_identificador(*id)).collect();
+            tonos::guardar_png_datos(&ruta(pasada, "png"), ancho, alto, &colores)?;
+            if exr {
+                let valores: Vec<_> = ids.iter().map(|id| identificador_exr(*id)).collect();
+                tonos::guardar_imagen(&ruta(pasada, "exr"), ancho, alto, &valores, &lineal)?;
+            }
+        }
+
+        if exr {
+            let profundidad: Vec<_> = self.pixeles.iter().map(|p| Vector3::repeat(p.profundidad)).collect();
+            tonos::guardar_imagen(&ruta("profundidad", "exr"), ancho, alto, &profundidad, &lineal)?;
+            tonos::guardar_imagen(&ruta("normal", "exr"), ancho, alto, &self.normales(), &lineal)?;
+            tonos::guardar_imagen(&ruta("albedo", "exr"), ancho, alto, &self.albedos(), &lineal)?;
+            tonos::guardar_imagen(&ruta("sombra", "exr"), ancho, alto, &sombra, &lineal)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use nalgebra::Vector3;
+use crate::pasadas::PasadasAuxiliares;
+use crate::renderizador::para_cada_pixel;
+
+// Núcleo B3-spline de 5 tomas del filtro À-trous
+const NUCLEO: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Parámetros del filtro À-trous guiado por las pasadas de albedo y normal
+#[derive(Clone, Copy, Debug)]
+pub struct ConfiguracionRuido {
+    pub iteraciones: u32,       // Cada iteración duplica el alcance (1, 2, 4, 8... píxeles)
+    pub sigma_color: f64,       // Tolerancia a diferencias de iluminación
+    pub sigma_normal: f64,
+    pub sigma_albedo: f64,
+}
+
+impl Default for ConfiguracionRuido {
+    fn default() -> Self {
+        Self {
+            iteraciones: 5,
+            sigma_color: 0.5,
+            sigma_normal: 0.3,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+// Compresión suave para que los píxeles muy brillantes no dominen la comparación
+fn comprimir(color: &Vector3<f64>) -> Vector3<f64> {
+    color.map(|c| c.max(0.0) / (1.0 + c.max(0.0)))
+}
+
+/// Filtro À-trous con pesos de borde (Dammertz et al. 2010). La iluminación se separa
+/// del albedo antes de filtrar, así las texturas quedan nítidas y solo se suaviza el ruido.
+/// Sirve para cualquier imagen del mismo tamaño que las pasadas.
+pub fn reducir_ruido(imagen: &[Vector3<f64>], guias: &PasadasAuxiliares,
+                     config: &ConfiguracionRuido) -> Vec<Vector3<f64>> {
+    let (ancho, alto) = (guias.ancho, guias.alto);
+    if imagen.len() != ancho * alto {
+        return imagen.to_vec();
+    }
+
+    // Dividir por el albedo (con un mínimo para no amplificar el ruido de lo oscuro)
+    let albedo_seguro = |i: usize| guias.pixeles[i].albedo.map(|c| c.max(0.02));
+    let mut iluminacion: Vec<Vector3<f64>> = imagen
+        .iter()
+        .enumerate()
+        .map(|(i, color)| color.component_div(&albedo_seguro(i)))
+        .collect();
+
+    for iteracion in 0..config.iteraciones {
+        let paso = 1isize << iteracion;
+        // La tolerancia de color se estrecha a medida que el filtro se ensancha
+        let sigma_color = config.sigma_color / 2f64.powi(iteracion as i32);
+        let anterior = &iluminacion;
+        let mut siguiente = vec![Vector3::zeros(); ancho * alto];
+
+        para_cada_pixel(&mut siguiente, ancho, alto, |x, y, salida, _| {
+            let centro = y * ancho + x;
+            let guia = &guias.pixeles[centro];
+            let color_centro = comprimir(&anterior[centro]);
+
+            let mut suma = Vector3::zeros();
+            let mut peso_total = 0.0;
+            for (j, kj) in NUCLEO.iter().enumerate() {
+                let vy = y as isize + (j as isize - 2) * paso;
+                if vy < 0 || vy >= alto as isize {
+                    continue;
+                }
+                for (i, ki) in NUCLEO.iter().enumerate() {
+                    let vx = x as isize + (i as isize - 2) * paso;
+                    if vx < 0 || vx >= ancho as isize {
+                        continue;
+                    }
+                    let vecino = vy as usize * ancho + vx as usize;
+                    let otra = &guias.pixeles[vecino];
+
+                    // El fondo y la geometría no se mezclan
+                    if (guia.id_objeto == 0) != (otra.id_objeto == 0) {
+                        continue;
+                    }
+
+                    let d_color = (comprimir(&anterior[vecino]) - color_centro).norm_squared();
+                    let d_normal = (otra.normal - guia.normal).norm_squared();
+                    let d_albedo = (otra.albedo - guia.albedo).norm_squared();
+                    let peso = ki * kj
+                        * (-d_color / (sigma_color * sigma_color)).exp()
+                        * (-d_normal / (config.sigma_normal * config.sigma_normal)).exp()
+                        * (-d_albedo / (config.sigma_albedo * config.sigma_albedo)).exp();
+
+                    suma += anterior[vecino] * peso;
+                    peso_total += peso;
+                }
+            }
+
+            *salida = if peso_total > 0.0 { suma / peso_total } else { anterior[centro] };
+        });
+
+        iluminacion = siguiente;
+    }
+
+    iluminacion
+        .iter()
+        .enumerate()
+        .map(|(i, luz)| luz.component_mul(&albedo_seguro(i)))
+        .collect()
+}
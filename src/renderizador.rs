@@ -0,0 +1,502 @@
+use nalgebra::Vector3;
+use crate::camara::Camara;
+use crate::escena::Escena;
+use crate::geometria::*;
+use crate::iluminacion::muestrear_coseno;
+use crate::muestreo::Muestreador;
+use rand::{rngs::ThreadRng, thread_rng, Rng};
+use std::f64::consts::PI;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const PROFUNDIDAD_MAXIMA: u32 = 5;
+pub const REBOTES_MAXIMOS: u32 = 12;
+const REBOTES_SIN_RULETA: u32 = 3;
+const EPSILON: f64 = 1e-4;
+const LUZ_AMBIENTE: f64 = 0.05;
+
+/// Integrador usado para calcular cada rayo de cámara
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ModoRender {
+    /// Rápido: luz directa, reflexión y refracción con luz ambiente constante
+    Whitted,
+    /// Iluminación global: rebotes difusos, muestreo de luces y MIS
+    TrazadoCaminos,
+}
+
+// Degradado de cielo para los rayos que no golpean nada
+pub fn color_fondo(rayo: &Rayo) -> Vector3<f64> {
+    let t = 0.5 * (rayo.direccion.y + 1.0);
+    Vector3::new(1.0, 1.0, 1.0) * (1.0 - t) + Vector3::new(0.53, 0.81, 0.92) * t
+}
+
+fn reflejar(direccion: &Vector3<f64>, normal: &Vector3<f64>) -> Vector3<f64> {
+    direccion - 2.0 * direccion.dot(normal) * normal
+}
+
+fn refractar(direccion: &Vector3<f64>, normal: &Vector3<f64>, indice: f64) -> Option<Vector3<f64>> {
+    let mut coseno = direccion.dot(normal);
+    let (n, eta) = if coseno < 0.0 {
+        coseno = -coseno;
+        (*normal, 1.0 / indice)
+    } else {
+        (-normal, indice)
+    };
+
+    let k = 1.0 - eta * eta * (1.0 - coseno * coseno);
+    if k < 0.0 {
+        None // Reflexión interna total
+    } else {
+        Some(eta * direccion + (eta * coseno - k.sqrt()) * n)
+    }
+}
+
+// Heurística de potencia (β = 2) para combinar dos estrategias de muestreo
+fn peso_mis(pdf_a: f64, pdf_b: f64) -> f64 {
+    let a2 = pdf_a * pdf_a;
+    let b2 = pdf_b * pdf_b;
+    if a2 + b2 > 0.0 { a2 / (a2 + b2) } else { 0.0 }
+}
+
+/// Luz directa difusa en el punto: muestrea cada luz y lanza un rayo de sombra.
+/// Con `mis` las luces no delta se ponderan frente al muestreo coseno de la BRDF.
+pub fn iluminacion_directa<R: Rng>(escena: &Escena, interseccion: &Interseccion, mis: bool,
+                                   rng: &mut R) -> Vector3<f64> {
+    let mut total = Vector3::zeros();
+    let origen = interseccion.punto + interseccion.normal * EPSILON;
+
+    for luz in &escena.luces {
+        let muestra = match luz.muestrear(&interseccion.punto, &interseccion.normal, rng) {
+            Some(muestra) => muestra,
+            None => continue,
+        };
+
+        let coseno = interseccion.normal.dot(&muestra.direccion);
+        if coseno <= 0.0 {
+            continue;
+        }
+
+        let rayo_sombra = Rayo::new(origen, muestra.direccion);
+        let transmitancia = escena.transmitancia(&rayo_sombra, muestra.distancia - 0.01);
+        if transmitancia.max() <= 0.0 {
+            continue;
+        }
+
+        let peso = if mis && !luz.es_delta() { peso_mis(muestra.pdf, coseno / PI) } else { 1.0 };
+        total += muestra.radiancia.component_mul(&transmitancia) * (peso * coseno / muestra.pdf);
+    }
+
+    // BRDF lambertiana: albedo / π
+    interseccion.material.albedo.component_mul(&total) / PI
+}
+
+// Trazado estilo Whitted: luz directa + reflexión + refracción
+pub fn trazar_rayo<R: Rng>(escena: &Escena, rayo: &Rayo, profundidad: u32, rng: &mut R) -> Vector3<f64> {
+    if profundidad == 0 {
+        return Vector3::zeros();
+    }
+
+    let interseccion = match escena.intersectar(rayo) {
+        Some(interseccion) => interseccion,
+        None => return radiancia_fondo(escena, rayo),
+    };
+    let material = &interseccion.material;
+
+    // Los huecos de las hojas dejan seguir al rayo
+    if !material.es_opaco_en(&interseccion.punto, &interseccion.normal) {
+        let siguiente = Rayo::new(interseccion.punto + rayo.direccion * EPSILON, rayo.direccion);
+        return trazar_rayo(escena, &siguiente, profundidad - 1, rng);
+    }
+
+    // Los bloques emisivos brillan por sí mismos
+    let mut color = material.emision;
+
+    let local = iluminacion_directa(escena, &interseccion, false, rng) + material.albedo * LUZ_AMBIENTE;
+    let peso_local = (1.0 - material.reflectividad - material.transparencia).max(0.0);
+    color += local * peso_local;
+
+    if material.reflectividad > 0.0 {
+        let direccion = reflejar(&rayo.direccion, &interseccion.normal);
+        let reflejado = Rayo::new(interseccion.punto + interseccion.normal * EPSILON, direccion);
+        color += trazar_rayo(escena, &reflejado, profundidad - 1, rng) * material.reflectividad;
+    }
+
+    if material.transparencia > 0.0 {
+        let direccion = refractar(&rayo.direccion, &interseccion.normal, material.indice_refraccion)
+            .unwrap_or_else(|| reflejar(&rayo.direccion, &interseccion.normal));
+        let refractado = Rayo::new(interseccion.punto + direccion * EPSILON, direccion);
+        let transmitido = trazar_rayo(escena, &refractado, profundidad - 1, rng);
+        color += transmitido.component_mul(&material.albedo) * material.transparencia;
+    }
+
+    color
+}
+
+// Luz de las luces de cielo que llega por una dirección sin obstáculos
+fn radiancia_cielo(escena: &Escena, direccion: &Vector3<f64>) -> Option<Vector3<f64>> {
+    radiancia_cielo_mis(escena, direccion, None)
+}
+
+// Igual que `radiancia_cielo`, pero si la dirección salió de un rebote difuso con
+// densidad `pdf_brdf`, cada cielo se pondera con MIS frente a su propio muestreo
+fn radiancia_cielo_mis(escena: &Escena, direccion: &Vector3<f64>, pdf_brdf: Option<f64>) -> Option<Vector3<f64>> {
+    let mut total = None;
+    for luz in escena.luces.iter().filter(|luz| luz.es_cielo()) {
+        let peso = match pdf_brdf {
+            Some(pdf) => peso_mis(pdf, luz.pdf_cielo(direccion, pdf)),
+            None => 1.0,
+        };
+        *total.get_or_insert(Vector3::zeros()) += luz.radiancia_cielo(direccion) * peso;
+    }
+    total
+}
+
+fn radiancia_discos(escena: &Escena, direccion: &Vector3<f64>) -> Vector3<f64> {
+    escena.luces.iter().map(|luz| luz.radiancia_disco(direccion)).sum()
+}
+
+/// Lo que ve un rayo de cámara o especular que no golpea nada: el cielo
+/// (o el degradado por defecto si la escena no tiene luz de cielo) y los discos solares
+pub fn radiancia_fondo(escena: &Escena, rayo: &Rayo) -> Vector3<f64> {
+    radiancia_cielo(escena, &rayo.direccion).unwrap_or_else(|| color_fondo(rayo))
+        + radiancia_discos(escena, &rayo.direccion)
+}
+
+/// Trazado de caminos unidireccional: rebotes difusos con muestreo coseno,
+/// estimación de evento siguiente hacia `escena.luces`, MIS y ruleta rusa.
+pub fn trazar_camino<R: Rng>(escena: &Escena, rayo: &Rayo, rng: &mut R) -> Vector3<f64> {
+    let mut radiancia = Vector3::zeros();
+    let mut rendimiento = Vector3::new(1.0, 1.0, 1.0);
+    let mut rayo = Rayo::new(rayo.origen, rayo.direccion);
+    // Datos del rebote anterior para ponderar la emisión golpeada con MIS
+    let mut pdf_brdf_previa = 0.0;
+    let mut especular_previo = true;
+    let mut rebote = 0;
+
+    while rebote < REBOTES_MAXIMOS {
+        let interseccion = match escena.intersectar(&rayo) {
+            Some(interseccion) => interseccion,
+            None => {
+                let cielo = if especular_previo {
+                    radiancia_fondo(escena, &rayo)
+                } else {
+                    // La luz de cielo ya se muestreó en el rebote difuso anterior
+                    radiancia_cielo_mis(escena, &rayo.direccion, Some(pdf_brdf_previa))
+                        .unwrap_or_else(|| color_fondo(&rayo))
+                };
+                radiancia += rendimiento.component_mul(&cielo);
+                break;
+            }
+        };
+        let material = &interseccion.material;
+
+        // Los huecos de las hojas no cuentan como rebote
+        if !material.es_opaco_en(&interseccion.punto, &interseccion.normal) {
+            rayo = Rayo::new(interseccion.punto + rayo.direccion * EPSILON, rayo.direccion);
+            continue;
+        }
+
+        if material.es_emisivo() {
+            let peso = if especular_previo {
+                1.0
+            } else {
+                let pdf_luz = escena.pdf_luz(&rayo.origen, &interseccion);
+                peso_mis(pdf_brdf_previa, pdf_luz)
+            };
+            radiancia += rendimiento.component_mul(&material.emision) * peso;
+        }
+
+        // Elegir lóbulo: espejo, transmisión o difuso según el material
+        let r: f64 = rng.gen();
+        if r < material.reflectividad {
+            let direccion = reflejar(&rayo.direccion, &interseccion.normal);
+            rayo = Rayo::new(interseccion.punto + interseccion.normal * EPSILON, direccion);
+            especular_previo = true;
+        } else if r < material.reflectividad + material.transparencia {
+            let direccion = refractar(&rayo.direccion, &interseccion.normal, material.indice_refraccion)
+                .unwrap_or_else(|| reflejar(&rayo.direccion, &interseccion.normal));
+            rayo = Rayo::new(interseccion.punto + direccion * EPSILON, direccion);
+            rendimiento = rendimiento.component_mul(&material.albedo);
+            especular_previo = true;
+        } else {
+            // Orientar la normal hacia el lado por el que llega el rayo
+            let mut interseccion = interseccion;
+            if interseccion.normal.dot(&rayo.direccion) > 0.0 {
+                interseccion.normal = -interseccion.normal;
+            }
+
+            radiancia += rendimiento.component_mul(&iluminacion_directa(escena, &interseccion, true, rng));
+
+            // Con muestreo coseno, BRDF · coseno / pdf = albedo
+            let direccion = muestrear_coseno(&interseccion.normal, rng);
+            pdf_brdf_previa = direccion.dot(&interseccion.normal).max(0.0) / PI;
+            rendimiento = rendimiento.component_mul(&interseccion.material.albedo);
+            rayo = Rayo::new(interseccion.punto + interseccion.normal * EPSILON, direccion);
+            especular_previo = false;
+        }
+
+        rebote += 1;
+
+        // Ruleta rusa: terminar caminos que ya aportan poco sin sesgar el resultado
+        if rebote >= REBOTES_SIN_RULETA {
+            let probabilidad = rendimiento.max().min(0.95);
+            if rng.gen::<f64>() >= probabilidad {
+                break;
+            }
+            rendimiento /= probabilidad;
+        }
+    }
+
+    radiancia
+}
+
+/// Calcula la radiancia de un rayo de cámara con el integrador elegido
+pub fn trazar<R: Rng>(escena: &Escena, rayo: &Rayo, modo: ModoRender, rng: &mut R) -> Vector3<f64> {
+    match modo {
+        ModoRender::Whitted => trazar_rayo(escena, rayo, PROFUNDIDAD_MAXIMA, rng),
+        ModoRender::TrazadoCaminos => trazar_camino(escena, rayo, rng),
+    }
+}
+
+// Reparte las filas del búfer entre hilos y aplica `funcion(x, y, pixel, rng)` a cada píxel
+pub fn para_cada_pixel<T, F>(pixeles: &mut [T], ancho: usize, alto: usize, funcion: F)
+where
+    T: Send,
+    F: Fn(usize, usize, &mut T, &mut ThreadRng) + Sync,
+{
+    let hilos = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let filas_por_hilo = alto.div_ceil(hilos).max(1);
+    let funcion = &funcion;
+
+    thread::scope(|ambito| {
+        for (bloque, trozo) in pixeles.chunks_mut(filas_por_hilo * ancho).enumerate() {
+            ambito.spawn(move || {
+                let mut rng = thread_rng();
+                for (i, pixel) in trozo.iter_mut().enumerate() {
+                    let x = i % ancho;
+                    let y = bloque * filas_por_hilo + i / ancho;
+                    funcion(x, y, pixel, &mut rng);
+                }
+            });
+        }
+    });
+}
+
+// Rayo por la posición (px, py) de la imagen, en píxeles (fila 0 = arriba)
+pub fn rayo_imagen(camara: &Camara, px: f64, py: f64, ancho: usize, alto: usize) -> Rayo {
+    let s = px / ancho as f64;
+    let t = 1.0 - py / alto as f64;
+    camara.obtener_rayo(s, t)
+}
+
+// Muestra trazada: posición en la imagen (en píxeles) y radiancia
+struct MuestraPelicula {
+    x: f64,
+    y: f64,
+    radiancia: Vector3<f64>,
+}
+
+// Media y varianza de la luminancia de las muestras de un píxel (Welford)
+#[derive(Clone, Copy, Default)]
+struct Varianza {
+    n: u32,
+    media: f64,
+    m2: f64,
+}
+
+impl Varianza {
+    fn agregar(&mut self, valor: f64) {
+        self.n += 1;
+        let delta = valor - self.media;
+        self.media += delta / self.n as f64;
+        self.m2 += delta * (valor - self.media);
+    }
+
+    // Error estándar de la media relativo a su brillo
+    fn error_relativo(&self) -> f64 {
+        if self.n < 2 {
+            return f64::INFINITY;
+        }
+        let varianza = self.m2 / (self.n - 1) as f64;
+        (varianza / self.n as f64).sqrt() / (self.media + 0.01)
+    }
+}
+
+// Muestras de la pasada actual y estadística acumulada de un píxel
+#[derive(Default)]
+struct PixelPasada {
+    muestras: Vec<MuestraPelicula>,
+    varianza: Varianza,
+}
+
+/// Criterios de parada del muestreo adaptativo
+#[derive(Clone, Copy, Debug)]
+pub struct ConfiguracionAdaptativa {
+    pub umbral_ruido: f64,          // Error relativo con el que un píxel se da por convergido
+    pub muestras_minimas: u32,
+    pub muestras_maximas: u32,
+    pub tiempo_limite: Option<Duration>,
+}
+
+impl Default for ConfiguracionAdaptativa {
+    fn default() -> Self {
+        Self {
+            umbral_ruido: 0.02,
+            muestras_minimas: 16,
+            muestras_maximas: 1024,
+            tiempo_limite: None,
+        }
+    }
+}
+
+/// Acumula pasadas para refinar la imagen poco a poco. Cada muestra se reparte
+/// entre los píxeles vecinos con el filtro de reconstrucción del muestreador.
+pub struct RenderProgresivo {
+    pub ancho: usize,
+    pub alto: usize,
+    pub muestras: u32,           // Máximo de muestras acumuladas en un píxel
+    pub muestreo: Muestreador,
+    suma: Vec<(Vector3<f64>, f64)>,   // Radiancia ponderada y suma de pesos
+    pasada: Vec<PixelPasada>,
+}
+
+impl RenderProgresivo {
+    pub fn nuevo(ancho: usize, alto: usize) -> Self {
+        Self {
+            ancho,
+            alto,
+            muestras: 0,
+            muestreo: Muestreador::default(),
+            suma: vec![(Vector3::zeros(), 0.0); ancho * alto],
+            pasada: (0..ancho * alto).map(|_| PixelPasada::default()).collect(),
+        }
+    }
+
+    pub fn con_muestreo(mut self, muestreo: Muestreador) -> Self {
+        self.muestreo = muestreo;
+        self
+    }
+
+    // Descarta lo acumulado (por ejemplo, al mover la cámara)
+    pub fn reiniciar(&mut self) {
+        self.muestras = 0;
+        self.suma.iter_mut().for_each(|p| *p = (Vector3::zeros(), 0.0));
+        self.pasada.iter_mut().for_each(|p| p.varianza = Varianza::default());
+    }
+
+    /// Traza `muestreo.muestras_por_pixel` muestras por píxel y las acumula
+    pub fn agregar_pasada(&mut self, escena: &Escena, camara: &Camara, modo: ModoRender) {
+        self.pasada_filtrada(escena, camara, modo, None);
+    }
+
+    /// Como `agregar_pasada`, pero salta los píxeles ya convergidos.
+    /// Devuelve cuántos píxeles recibieron muestras.
+    pub fn agregar_pasada_adaptativa(&mut self, escena: &Escena, camara: &Camara, modo: ModoRender,
+                                     config: &ConfiguracionAdaptativa) -> usize {
+        self.pasada_filtrada(escena, camara, modo, Some(config))
+    }
+
+    /// Repite pasadas adaptativas hasta que todos los píxeles convergen,
+    /// llegan al máximo de muestras o se agota el tiempo
+    pub fn renderizar_adaptativo(&mut self, escena: &Escena, camara: &Camara, modo: ModoRender,
+                                 config: &ConfiguracionAdaptativa) {
+        let inicio = Instant::now();
+        loop {
+            let activos = self.agregar_pasada_adaptativa(escena, camara, modo, config);
+            let agotado = config.tiempo_limite.is_some_and(|limite| inicio.elapsed() >= limite);
+            if activos == 0 || agotado {
+                break;
+            }
+        }
+    }
+
+    fn pasada_filtrada(&mut self, escena: &Escena, camara: &Camara, modo: ModoRender,
+                       adaptativo: Option<&ConfiguracionAdaptativa>) -> usize {
+        let (ancho, alto) = (self.ancho, self.alto);
+        let muestreo = self.muestreo;
+        let por_pixel = muestreo.muestras_por_pixel.max(1);
+
+        para_cada_pixel(&mut self.pasada, ancho, alto, |x, y, pixel, rng| {
+            pixel.muestras.clear();
+            if let Some(config) = adaptativo {
+                let n = pixel.varianza.n;
+                let convergido = n >= config.muestras_minimas
+                    && pixel.varianza.error_relativo() < config.umbral_ruido;
+                if convergido || n >= config.muestras_maximas {
+                    return;
+                }
+            }
+
+            let primera = pixel.varianza.n;
+            for k in 0..por_pixel {
+                let (u, v) = muestreo.muestra(x, y, primera + k, por_pixel, rng);
+                let (px, py) = (x as f64 + u, y as f64 + v);
+                let rayo = rayo_imagen(camara, px, py, ancho, alto);
+                let radiancia = trazar(escena, &rayo, modo, rng);
+                pixel.varianza.agregar(luminancia(&radiancia));
+                pixel.muestras.push(MuestraPelicula { x: px, y: py, radiancia });
+            }
+        });
+
+        // Cada píxel recoge las muestras de sus vecinos que caen dentro del filtro
+        let pasada = &self.pasada;
+        let filtro = muestreo.filtro;
+        let alcance = (filtro.radio() - 0.5).ceil().max(0.0) as isize;
+        para_cada_pixel(&mut self.suma, ancho, alto, |x, y, (suma, peso), _| {
+            let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+            for vy in (y as isize - alcance).max(0)..=(y as isize + alcance).min(alto as isize - 1) {
+                for vx in (x as isize - alcance).max(0)..=(x as isize + alcance).min(ancho as isize - 1) {
+                    for muestra in &pasada[vy as usize * ancho + vx as usize].muestras {
+                        let w = filtro.evaluar(muestra.x - cx, muestra.y - cy);
+                        if w != 0.0 {
+                            *suma += muestra.radiancia * w;
+                            *peso += w;
+                        }
+                    }
+                }
+            }
+        });
+
+        let activos = self.pasada.iter().filter(|p| !p.muestras.is_empty()).count();
+        if activos > 0 {
+            self.muestras = self.pasada.iter().map(|p| p.varianza.n).max().unwrap_or(0);
+        }
+        activos
+    }
+
+    /// Imagen reconstruida con lo acumulado hasta ahora
+    pub fn imagen(&self) -> Vec<Vector3<f64>> {
+        self.suma
+            .iter()
+            .map(|(suma, peso)| if *peso > 1e-8 { suma / *peso } else { Vector3::zeros() })
+            .collect()
+    }
+
+    /// Mapa de calor de muestras gastadas por píxel (azul = pocas, rojo = el máximo)
+    pub fn mapa_muestras(&self) -> Vec<Vector3<f64>> {
+        let maximo = self.muestras.max(1) as f64;
+        self.pasada
+            .iter()
+            .map(|p| color_calor(p.varianza.n as f64 / maximo))
+            .collect()
+    }
+}
+
+fn luminancia(color: &Vector3<f64>) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+// Rampa azul -> cian -> verde -> amarillo -> rojo para t en [0, 1]
+fn color_calor(t: f64) -> Vector3<f64> {
+    let paradas = [
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(1.0, 1.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+    ];
+    let posicion = t.clamp(0.0, 1.0) * (paradas.len() - 1) as f64;
+    let i = (posicion.floor() as usize).min(paradas.len() - 2);
+    let f = posicion - i as f64;
+    paradas[i] * (1.0 - f) + paradas[i + 1] * f
+}
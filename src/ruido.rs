@@ -0,0 +1,146 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::f64::consts::FRAC_1_SQRT_2;
+
+// Gradientes de Perlin 2D: las 8 direcciones de la rosa de los vientos
+const GRADIENTES_2D: [(f64, f64); 8] = [
+    (1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0),
+    (FRAC_1_SQRT_2, FRAC_1_SQRT_2), (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2), (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+// Gradientes de Perlin 3D: los 12 puntos medios de las aristas de un cubo
+const GRADIENTES_3D: [(f64, f64, f64); 12] = [
+    (1.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (1.0, -1.0, 0.0), (-1.0, -1.0, 0.0),
+    (1.0, 0.0, 1.0), (-1.0, 0.0, 1.0), (1.0, 0.0, -1.0), (-1.0, 0.0, -1.0),
+    (0.0, 1.0, 1.0), (0.0, -1.0, 1.0), (0.0, 1.0, -1.0), (0.0, -1.0, -1.0),
+];
+
+/// Ruido de gradiente de Perlin con semilla: el mismo valor para la misma semilla y posición
+#[derive(Clone)]
+pub struct Perlin {
+    permutacion: [u8; 512],        // Tabla de 256 valores barajada y repetida
+}
+
+// Curva de interpolación 6t^5 - 15t^4 + 10t^3 (derivadas continuas en los bordes)
+fn suavizar(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn mezclar(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+impl Perlin {
+    pub fn nuevo(semilla: u64) -> Self {
+        let mut valores: Vec<u8> = (0..=255).collect();
+        valores.shuffle(&mut StdRng::seed_from_u64(semilla));
+
+        let mut permutacion = [0u8; 512];
+        for (i, p) in permutacion.iter_mut().enumerate() {
+            *p = valores[i & 255];
+        }
+        Self { permutacion }
+    }
+
+    fn hash(&self, x: i64, y: i64) -> usize {
+        let a = self.permutacion[(x & 255) as usize] as usize;
+        self.permutacion[a + (y & 255) as usize] as usize
+    }
+
+    fn hash3(&self, x: i64, y: i64, z: i64) -> usize {
+        let a = self.hash(x, y);
+        self.permutacion[a + (z & 255) as usize] as usize
+    }
+
+    /// Ruido 2D aproximadamente en [-1, 1]; vale 0 en los puntos enteros
+    pub fn ruido2(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i64, y0 as i64);
+
+        let esquina = |dx: i64, dy: i64| {
+            let (gx, gy) = GRADIENTES_2D[self.hash(ix + dx, iy + dy) & 7];
+            gx * (fx - dx as f64) + gy * (fy - dy as f64)
+        };
+
+        let (u, v) = (suavizar(fx), suavizar(fy));
+        let abajo = mezclar(esquina(0, 0), esquina(1, 0), u);
+        let arriba = mezclar(esquina(0, 1), esquina(1, 1), u);
+        // √2 lleva el máximo teórico (√½) a 1
+        mezclar(abajo, arriba, v) * std::f64::consts::SQRT_2
+    }
+
+    /// Ruido 3D aproximadamente en [-1, 1]; vale 0 en los puntos enteros
+    pub fn ruido3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+        let (ix, iy, iz) = (x0 as i64, y0 as i64, z0 as i64);
+
+        let esquina = |dx: i64, dy: i64, dz: i64| {
+            let (gx, gy, gz) = GRADIENTES_3D[self.hash3(ix + dx, iy + dy, iz + dz) % 12];
+            gx * (fx - dx as f64) + gy * (fy - dy as f64) + gz * (fz - dz as f64)
+        };
+
+        let (u, v, w) = (suavizar(fx), suavizar(fy), suavizar(fz));
+        let cara = |dz: i64| {
+            let abajo = mezclar(esquina(0, 0, dz), esquina(1, 0, dz), u);
+            let arriba = mezclar(esquina(0, 1, dz), esquina(1, 1, dz), u);
+            mezclar(abajo, arriba, v)
+        };
+        mezclar(cara(0), cara(1), w)
+    }
+
+    /// Ruido fractal (fBm): suma de `octavas` capas, cada una con el doble de
+    /// frecuencia y la mitad de amplitud. Normalizado a [-1, 1].
+    pub fn fractal2(&self, x: f64, y: f64, octavas: u32) -> f64 {
+        let (mut suma, mut amplitud, mut frecuencia, mut total) = (0.0, 1.0, 1.0, 0.0);
+        for octava in 0..octavas {
+            // Desplazar cada octava evita que todas valgan 0 en el origen
+            let desfase = octava as f64 * 17.31;
+            suma += self.ruido2(x * frecuencia + desfase, y * frecuencia - desfase) * amplitud;
+            total += amplitud;
+            amplitud *= 0.5;
+            frecuencia *= 2.0;
+        }
+        suma / total
+    }
+
+    /// fBm 3D, como `fractal2`
+    pub fn fractal3(&self, x: f64, y: f64, z: f64, octavas: u32) -> f64 {
+        let (mut suma, mut amplitud, mut frecuencia, mut total) = (0.0, 1.0, 1.0, 0.0);
+        for octava in 0..octavas {
+            let desfase = octava as f64 * 17.31;
+            suma += self.ruido3(x * frecuencia + desfase, y * frecuencia, z * frecuencia - desfase) * amplitud;
+            total += amplitud;
+            amplitud *= 0.5;
+            frecuencia *= 2.0;
+        }
+        suma / total
+    }
+
+    /// fBm de crestas: 1 - |ruido| forma cordilleras afiladas. En [0, 1].
+    pub fn crestas2(&self, x: f64, y: f64, octavas: u32) -> f64 {
+        let (mut suma, mut amplitud, mut frecuencia, mut total) = (0.0, 1.0, 1.0, 0.0);
+        for octava in 0..octavas {
+            let desfase = octava as f64 * 17.31;
+            let cresta = 1.0 - self.ruido2(x * frecuencia + desfase, y * frecuencia - desfase).abs();
+            suma += cresta * cresta * amplitud;
+            total += amplitud;
+            amplitud *= 0.5;
+            frecuencia *= 2.0;
+        }
+        suma / total
+    }
+}
+
+/// Número pseudoaleatorio en [0, 1) fijo para una semilla y una celda entera
+pub fn aleatorio_celda(semilla: u64, x: i32, z: i32) -> f64 {
+    let mut h = semilla ^ (x as u32 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (z as u32 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,344 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use crate::mundo::*;
+
+/// Nivel del agua quieta que no se agota
+pub const FUENTE: u8 = 0;
+/// Nivel del agua que cae desde el bloque de arriba
+pub const CAYENDO: u8 = 8;
+
+// Direcciones horizontales en las que corre el agua
+const HORIZONTALES: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+// Las seis caras de un bloque
+const VECINOS: [Posicion; 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+/// Reglas del agua
+#[derive(Clone, Debug)]
+pub struct ConfiguracionSimulacion {
+    pub alcance_agua: u8,          // Nivel del último bloque de agua que corre (1 a 7)
+    pub busqueda_hueco: i32,       // Bloques que mira el agua para correr hacia un hueco
+    pub fuentes_infinitas: bool,   // Dos fuentes vecinas crean otra entre ellas
+}
+
+impl Default for ConfiguracionSimulacion {
+    fn default() -> Self {
+        Self {
+            alcance_agua: 7,
+            busqueda_hueco: 4,
+            fuentes_infinitas: true,
+        }
+    }
+}
+
+/// Qué cambió en un tick
+#[derive(Clone, Debug, Default)]
+pub struct ResumenTick {
+    pub cambios: BTreeSet<Posicion>,   // Bloques que cambiaron de tipo o de nivel
+    pub caidos: usize,                 // Bloques de arena o grava que bajaron uno
+}
+
+/// Autómata celular del agua y de la arena y la grava sobre un mundo, a ticks fijos.
+/// Los bloques de agua sin nivel guardado son fuentes; el agua que corre baja un nivel
+/// por bloque (1 junto a la fuente, 7 el último) y prefiere ir hacia los huecos más
+/// cercanos. La arena y la grava caen un bloque por tick mientras debajo haya aire o agua.
+/// Solo se simulan las columnas de `minimo` a `maximo`; fuera de ellas todo es sólido.
+pub struct Simulacion {
+    pub minimo: (i32, i32),
+    pub maximo: (i32, i32),
+    pub config: ConfiguracionSimulacion,
+    pub ticks: u64,
+    niveles: HashMap<Posicion, u8>,    // Nivel del agua que corre (las fuentes no están)
+    pendientes: BTreeSet<Posicion>,    // Bloques a revisar en el próximo tick
+}
+
+impl Simulacion {
+    pub fn nueva(minimo: (i32, i32), maximo: (i32, i32)) -> Self {
+        Self::con_configuracion(minimo, maximo, ConfiguracionSimulacion::default())
+    }
+
+    pub fn con_configuracion(minimo: (i32, i32), maximo: (i32, i32), config: ConfiguracionSimulacion) -> Self {
+        Self {
+            minimo,
+            maximo,
+            config,
+            ticks: 0,
+            niveles: HashMap::new(),
+            pendientes: BTreeSet::new(),
+        }
+    }
+
+    /// Programa la revisión de toda el agua y de los bloques que caen de la región
+    pub fn activar(&mut self, mundo: &Mundo) {
+        let activos: Vec<Posicion> = mundo
+            .bloques()
+            .filter(|&(posicion, id)| matches!(id, AGUA | ARENA | GRAVA) && self.dentro(mundo, posicion))
+            .map(|(posicion, _)| posicion)
+            .collect();
+        self.pendientes.extend(activos);
+    }
+
+    /// Pone un bloque (por ejemplo una fuente de agua) y despierta a sus vecinos
+    pub fn colocar(&mut self, mundo: &mut Mundo, posicion: Posicion, id: IdBloque) {
+        let (x, y, z) = posicion;
+        if mundo.colocar(x, y, z, id) {
+            self.niveles.remove(&posicion);
+            self.despertar(posicion);
+        }
+    }
+
+    /// Nivel del agua del bloque (`None` si no es agua)
+    pub fn nivel(&self, mundo: &Mundo, posicion: Posicion) -> Option<u8> {
+        let (x, y, z) = posicion;
+        (mundo.obtener(x, y, z) == AGUA).then(|| self.niveles.get(&posicion).copied().unwrap_or(FUENTE))
+    }
+
+    /// Hay bloques por revisar: la simulación aún no se ha asentado
+    pub fn activa(&self) -> bool {
+        !self.pendientes.is_empty()
+    }
+
+    /// Avanza un tick: primero caen la arena y la grava y después corre el agua. El agua
+    /// se decide con el estado al empezar el tick, así avanza un bloque por tick en
+    /// todas las direcciones sin depender del orden en que se recorre.
+    pub fn paso(&mut self, mundo: &mut Mundo) -> ResumenTick {
+        self.ticks += 1;
+        let mut resumen = ResumenTick::default();
+        let pendientes = std::mem::take(&mut self.pendientes);
+
+        // De abajo arriba, para que una pila entera baje junta
+        let mut caen: Vec<Posicion> = pendientes
+            .iter()
+            .copied()
+            .filter(|&(x, y, z)| matches!(mundo.obtener(x, y, z), ARENA | GRAVA))
+            .collect();
+        caen.sort_by_key(|&(x, y, z)| (y, x, z));
+        for (x, y, z) in caen {
+            let debajo = (x, y - 1, z);
+            if !self.dentro(mundo, debajo) || !matches!(mundo.obtener(x, y - 1, z), AIRE | AGUA) {
+                continue;
+            }
+            let id = mundo.obtener(x, y, z);
+            mundo.colocar(x, y - 1, z, id);
+            mundo.colocar(x, y, z, AIRE);
+            self.niveles.remove(&debajo);
+            for posicion in [(x, y, z), debajo] {
+                self.despertar(posicion);
+                resumen.cambios.insert(posicion);
+            }
+            resumen.caidos += 1;
+        }
+
+        let nuevos: Vec<(Posicion, Option<u8>)> = pendientes
+            .iter()
+            .copied()
+            .filter(|&p| self.reemplazable(mundo, p))
+            .map(|p| (p, self.nivel_deseado(mundo, p)))
+            .filter(|&(p, nivel)| nivel != self.nivel(mundo, p))
+            .collect();
+        for (posicion, nivel) in nuevos {
+            let (x, y, z) = posicion;
+            match nivel {
+                Some(nivel) => {
+                    mundo.colocar(x, y, z, AGUA);
+                    if nivel == FUENTE {
+                        self.niveles.remove(&posicion);
+                    } else {
+                        self.niveles.insert(posicion, nivel);
+                    }
+                }
+                None => {
+                    mundo.colocar(x, y, z, AIRE);
+                    self.niveles.remove(&posicion);
+                }
+            }
+            self.despertar(posicion);
+            resumen.cambios.insert(posicion);
+        }
+        resumen
+    }
+
+    // Revisa el bloque y sus vecinos en el próximo tick
+    fn despertar(&mut self, (x, y, z): Posicion) {
+        self.pendientes.insert((x, y, z));
+        for (dx, dy, dz) in VECINOS {
+            self.pendientes.insert((x + dx, y + dy, z + dz));
+        }
+    }
+
+    fn dentro(&self, mundo: &Mundo, (x, y, z): Posicion) -> bool {
+        mundo.dentro_de_altura(y)
+            && (self.minimo.0..=self.maximo.0).contains(&x)
+            && (self.minimo.1..=self.maximo.1).contains(&z)
+    }
+
+    // El agua puede ocupar el bloque: aire o agua que corre (las fuentes no se tocan)
+    fn reemplazable(&self, mundo: &Mundo, posicion: Posicion) -> bool {
+        let (x, y, z) = posicion;
+        self.dentro(mundo, posicion)
+            && match mundo.obtener(x, y, z) {
+                AIRE => true,
+                AGUA => self.niveles.contains_key(&posicion),
+                _ => false,
+            }
+    }
+
+    // Nivel que corresponde a un bloque de aire o de agua que corre según sus vecinos
+    fn nivel_deseado(&self, mundo: &Mundo, posicion: Posicion) -> Option<u8> {
+        let (x, y, z) = posicion;
+        if self.nivel(mundo, (x, y + 1, z)).is_some() {
+            return Some(CAYENDO);
+        }
+
+        let apoyada = !self.reemplazable(mundo, (x, y - 1, z));
+        let fuentes = HORIZONTALES
+            .iter()
+            .filter(|&&(dx, dz)| self.nivel(mundo, (x + dx, y, z + dz)) == Some(FUENTE))
+            .count();
+        if self.config.fuentes_infinitas && apoyada && fuentes >= 2 {
+            return Some(FUENTE);
+        }
+
+        // El menor nivel que le llega de un vecino que corre hacia aquí
+        HORIZONTALES
+            .iter()
+            .filter_map(|&(dx, dz)| {
+                let origen = (x - dx, y, z - dz);
+                let nivel = self.nivel(mundo, origen)?;
+                let sale = if matches!(nivel, FUENTE | CAYENDO) { 1 } else { nivel + 1 };
+                let corre = sale <= self.config.alcance_agua
+                    && !self.reemplazable(mundo, (origen.0, y - 1, origen.2))
+                    && self.direcciones(mundo, origen).contains(&(dx, dz));
+                corre.then_some(sale)
+            })
+            .min()
+    }
+
+    // Direcciones en las que corre el agua apoyada en `origen`: las que llevan antes a un
+    // hueco por el que caer dentro de `busqueda_hueco` bloques, o todas si no hay ninguno
+    fn direcciones(&self, mundo: &Mundo, origen: Posicion) -> Vec<(i32, i32)> {
+        let (x, y, z) = origen;
+        let libres: Vec<(i32, i32)> = HORIZONTALES
+            .iter()
+            .copied()
+            .filter(|&(dx, dz)| self.reemplazable(mundo, (x + dx, y, z + dz)))
+            .collect();
+        let distancias: Vec<Option<i32>> = libres
+            .iter()
+            .map(|&(dx, dz)| self.distancia_hueco(mundo, (x + dx, y, z + dz), origen))
+            .collect();
+        match distancias.iter().flatten().min() {
+            Some(&minima) => libres
+                .into_iter()
+                .zip(distancias)
+                .filter(|&(_, distancia)| distancia == Some(minima))
+                .map(|(direccion, _)| direccion)
+                .collect(),
+            None => libres,
+        }
+    }
+
+    // Pasos desde `inicio` hasta el bloque más cercano con aire o agua que corre debajo,
+    // sin volver a pasar por `origen`
+    fn distancia_hueco(&self, mundo: &Mundo, inicio: Posicion, origen: Posicion) -> Option<i32> {
+        let mut visitados = BTreeSet::from([origen, inicio]);
+        let mut cola = VecDeque::from([(inicio, 0)]);
+        while let Some(((x, y, z), distancia)) = cola.pop_front() {
+            if self.reemplazable(mundo, (x, y - 1, z)) {
+                return Some(distancia);
+            }
+            if distancia >= self.config.busqueda_hueco {
+                continue;
+            }
+            for (dx, dz) in HORIZONTALES {
+                let vecino = (x + dx, y, z + dz);
+                if self.reemplazable(mundo, vecino) && visitados.insert(vecino) {
+                    cola.push_back((vecino, distancia + 1));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Losa de piedra de 21x21 en y = 0 y su simulación
+    fn losa() -> (Mundo, Simulacion) {
+        let mut mundo = Mundo::nuevo(16);
+        mundo.rellenar((-10, 0, -10), (10, 0, 10), PIEDRA);
+        (mundo, Simulacion::nueva((-10, -10), (10, 10)))
+    }
+
+    // Avanza hasta que se asienta; falla si no lo hace en `maximo` ticks
+    fn asentar(simulacion: &mut Simulacion, mundo: &mut Mundo, maximo: u64) {
+        for _ in 0..maximo {
+            if !simulacion.activa() {
+                return;
+            }
+            simulacion.paso(mundo);
+        }
+        assert!(!simulacion.activa(), "no se asentó en {} ticks", maximo);
+    }
+
+    #[test]
+    fn fuente_corre_hasta_el_alcance() {
+        let (mut mundo, mut simulacion) = losa();
+        simulacion.colocar(&mut mundo, (0, 1, 0), AGUA);
+        asentar(&mut simulacion, &mut mundo, 50);
+
+        let alcance = simulacion.config.alcance_agua as i32;
+        for x in -10i32..=10 {
+            for z in -10i32..=10 {
+                let distancia = x.abs() + z.abs();
+                let esperado = (distancia <= alcance).then_some(distancia as u8);
+                assert_eq!(simulacion.nivel(&mundo, (x, 1, z)), esperado, "({}, {})", x, z);
+            }
+        }
+        assert_eq!(simulacion.nivel(&mundo, (alcance + 1, 1, 0)), None);
+    }
+
+    #[test]
+    fn agua_cae_por_un_hueco() {
+        let mut mundo = Mundo::nuevo(16);
+        mundo.rellenar((-10, 0, -10), (10, 2, 10), PIEDRA);
+        mundo.rellenar((2, 1, 0), (2, 2, 0), AIRE);
+        let mut simulacion = Simulacion::nueva((-10, -10), (10, 10));
+        simulacion.colocar(&mut mundo, (0, 3, 0), AGUA);
+        asentar(&mut simulacion, &mut mundo, 50);
+
+        assert_eq!(simulacion.nivel(&mundo, (1, 3, 0)), Some(1));
+        assert_eq!(simulacion.nivel(&mundo, (2, 2, 0)), Some(CAYENDO));
+        assert_eq!(simulacion.nivel(&mundo, (2, 1, 0)), Some(CAYENDO));
+        // Solo corre hacia el hueco
+        assert_eq!(simulacion.nivel(&mundo, (-1, 3, 0)), None);
+    }
+
+    #[test]
+    fn agua_sin_fuente_se_seca() {
+        let (mut mundo, mut simulacion) = losa();
+        simulacion.colocar(&mut mundo, (0, 1, 0), AGUA);
+        asentar(&mut simulacion, &mut mundo, 50);
+
+        simulacion.colocar(&mut mundo, (0, 1, 0), AIRE);
+        asentar(&mut simulacion, &mut mundo, 200);
+        assert_eq!(mundo.bloques().filter(|&(_, id)| id == AGUA).count(), 0);
+    }
+
+    #[test]
+    fn pila_de_arena_y_grava_cae_junta() {
+        let (mut mundo, mut simulacion) = losa();
+        for (y, id) in [(5, GRAVA), (6, ARENA), (7, GRAVA)] {
+            mundo.colocar(0, y, 0, id);
+        }
+        simulacion.activar(&mundo);
+
+        assert_eq!(simulacion.paso(&mut mundo).caidos, 3);
+        assert_eq!(mundo.obtener(0, 7, 0), AIRE);
+        assert_eq!(mundo.obtener(0, 4, 0), GRAVA);
+        asentar(&mut simulacion, &mut mundo, 20);
+
+        let columna: Vec<IdBloque> = (1..=4).map(|y| mundo.obtener(0, y, 0)).collect();
+        assert_eq!(columna, [GRAVA, ARENA, GRAVA, AIRE]);
+    }
+}
@@ -0,0 +1,290 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::mundo::*;
+use crate::ruido::Perlin;
+
+// Área de un chunk en columnas: las densidades se dan por chunk
+const COLUMNAS_CHUNK: f64 = (TAMANO_CHUNK * TAMANO_CHUNK) as f64;
+
+/// Veta de mineral: grupos de bloques repartidos por profundidad bajo la superficie
+#[derive(Clone, Debug)]
+pub struct Veta {
+    pub id: IdBloque,
+    pub grupos_por_chunk: f64,
+    pub tamano: usize,             // Pasos del recorrido aleatorio que forma cada grupo
+    pub profundidad_min: i32,
+    pub profundidad_max: i32,
+    pub profundidad_pico: i32,     // Profundidad más frecuente
+}
+
+impl Veta {
+    /// Carbón: abundante, en grupos grandes y cerca de la superficie
+    pub fn carbon() -> Self {
+        Self {
+            id: CARBON,
+            grupos_por_chunk: 20.0,
+            tamano: 10,
+            profundidad_min: 4,
+            profundidad_max: 14,
+            profundidad_pico: 6,
+        }
+    }
+
+    /// Hierro: más escaso, en grupos pequeños y más hondo
+    pub fn hierro() -> Self {
+        Self {
+            id: HIERRO,
+            grupos_por_chunk: 12.0,
+            tamano: 6,
+            profundidad_min: 5,
+            profundidad_max: 18,
+            profundidad_pico: 10,
+        }
+    }
+}
+
+/// Forma de las cuevas y vetas; las distancias están en bloques
+#[derive(Clone, Debug)]
+pub struct ConfiguracionSubsuelo {
+    pub escala_cuevas: f64,        // Tamaño de las cámaras del ruido 3D
+    pub umbral_cuevas: f64,        // Se vacía donde el ruido supera este valor
+    pub techo: i32,                // Roca que el ruido respeta bajo la superficie
+    pub gusanos_por_chunk: f64,
+    pub longitud_gusano: usize,    // Pasos de un bloque
+    pub radio_gusano: f64,
+    pub vetas: Vec<Veta>,
+}
+
+impl Default for ConfiguracionSubsuelo {
+    fn default() -> Self {
+        Self {
+            escala_cuevas: 10.0,
+            umbral_cuevas: 0.2,
+            techo: 3,
+            gusanos_por_chunk: 3.0,
+            longitud_gusano: 40,
+            radio_gusano: 1.6,
+            vetas: vec![Veta::carbon(), Veta::hierro()],
+        }
+    }
+}
+
+/// Bloques cambiados por el generador
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResumenSubsuelo {
+    pub excavados: usize,
+    pub grupos_mineral: usize,
+    pub bloques_mineral: usize,
+}
+
+// Roca que las cuevas pueden vaciar
+fn excavable(id: IdBloque) -> bool {
+    matches!(id, PIEDRA | TIERRA | GRAVA | CARBON | HIERRO)
+}
+
+// Las cuevas no se abren junto al agua o la lava para no dejar líquidos colgando
+fn junto_a_liquido(mundo: &Mundo, x: i32, y: i32, z: i32) -> bool {
+    [(0, 0, 0), (1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)]
+        .iter()
+        .any(|&(dx, dy, dz)| matches!(mundo.obtener(x + dx, y + dy, z + dz), AGUA | LAVA))
+}
+
+// Muestra de una distribución triangular entre `minimo` y `maximo` con moda en `pico`
+fn triangular(rng: &mut StdRng, minimo: f64, pico: f64, maximo: f64) -> f64 {
+    if maximo <= minimo {
+        return minimo;
+    }
+    let u: f64 = rng.gen();
+    let corte = (pico - minimo) / (maximo - minimo);
+    if u < corte {
+        minimo + (u * (maximo - minimo) * (pico - minimo)).sqrt()
+    } else {
+        maximo - ((1.0 - u) * (maximo - minimo) * (maximo - pico)).sqrt()
+    }
+}
+
+/// Cuevas y minerales bajo un terreno ya generado. Reproducible para la semilla.
+pub struct GeneradorSubsuelo {
+    pub semilla: u64,
+    pub config: ConfiguracionSubsuelo,
+    cuevas: Perlin,
+}
+
+impl GeneradorSubsuelo {
+    pub fn nuevo(semilla: u64) -> Self {
+        Self::con_configuracion(semilla, ConfiguracionSubsuelo::default())
+    }
+
+    pub fn con_configuracion(semilla: u64, config: ConfiguracionSubsuelo) -> Self {
+        Self {
+            semilla,
+            config,
+            cuevas: Perlin::nuevo(semilla.wrapping_mul(31).wrapping_add(6)),
+        }
+    }
+
+    // Generador aleatorio propio de cada etapa, para que cambiar una no altere las demás
+    fn aleatorio(&self, etapa: u64) -> StdRng {
+        StdRng::seed_from_u64(self.semilla ^ etapa.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    // Número de elementos para una densidad por chunk en la región
+    fn cantidad(densidad: f64, minimo: (i32, i32), maximo: (i32, i32)) -> usize {
+        let columnas = ((maximo.0 - minimo.0 + 1) * (maximo.1 - minimo.1 + 1)).max(0) as f64;
+        (densidad * columnas / COLUMNAS_CHUNK).round() as usize
+    }
+
+    /// Cuevas y vetas en las columnas de `minimo` a `maximo` (x, z; ambos incluidos)
+    pub fn generar(&self, mundo: &mut Mundo, minimo: (i32, i32), maximo: (i32, i32)) -> ResumenSubsuelo {
+        let mut resumen = ResumenSubsuelo {
+            excavados: self.excavar_cavernas(mundo, minimo, maximo) + self.excavar_gusanos(mundo, minimo, maximo),
+            ..Default::default()
+        };
+        let (grupos, bloques) = self.colocar_vetas(mundo, minimo, maximo);
+        resumen.grupos_mineral = grupos;
+        resumen.bloques_mineral = bloques;
+        resumen
+    }
+
+    /// Cavernas de ruido 3D: se vacía la roca donde el ruido supera el umbral. La escala
+    /// vertical es la mitad de la horizontal, así las cámaras salen anchas y bajas.
+    pub fn excavar_cavernas(&self, mundo: &mut Mundo, minimo: (i32, i32), maximo: (i32, i32)) -> usize {
+        let c = &self.config;
+        let mut excavados = 0;
+        for x in minimo.0..=maximo.0 {
+            for z in minimo.1..=maximo.1 {
+                let superficie = match mundo.superficie(x, z) {
+                    Some(y) => y,
+                    None => continue,
+                };
+                for y in 1..=superficie - c.techo {
+                    let ruido = self.cuevas.fractal3(
+                        x as f64 / c.escala_cuevas,
+                        y as f64 * 2.0 / c.escala_cuevas,
+                        z as f64 / c.escala_cuevas,
+                        2,
+                    );
+                    if ruido > c.umbral_cuevas
+                        && excavable(mundo.obtener(x, y, z))
+                        && !junto_a_liquido(mundo, x, y, z)
+                    {
+                        mundo.colocar(x, y, z, AIRE);
+                        excavados += 1;
+                    }
+                }
+            }
+        }
+        excavados
+    }
+
+    /// Cuevas de gusano: túneles que avanzan girando poco a poco y vacían una esfera de
+    /// radio variable en cada paso. Pueden asomar a la superficie y abrir una entrada.
+    pub fn excavar_gusanos(&self, mundo: &mut Mundo, minimo: (i32, i32), maximo: (i32, i32)) -> usize {
+        let c = &self.config;
+        let mut rng = self.aleatorio(1);
+        let mut excavados = 0;
+
+        for _ in 0..Self::cantidad(c.gusanos_por_chunk, minimo, maximo) {
+            let x = rng.gen_range(minimo.0..=maximo.0);
+            let z = rng.gen_range(minimo.1..=maximo.1);
+            let superficie = mundo.superficie(x, z).unwrap_or(0);
+            if superficie < 5 {
+                continue;
+            }
+            let mut posicion = [x as f64 + 0.5, rng.gen_range(2..superficie - 2) as f64 + 0.5, z as f64 + 0.5];
+            let mut rumbo: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+            let mut inclinacion: f64 = rng.gen_range(-0.3..0.3);
+            let mut giro = 0.0;
+            let fase: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+
+            for paso in 0..c.longitud_gusano {
+                let radio = c.radio_gusano * (0.8 + 0.4 * (paso as f64 * 0.3 + fase).sin());
+                excavados += self.excavar_esfera(mundo, posicion, radio, minimo, maximo);
+
+                posicion[0] += rumbo.cos() * inclinacion.cos();
+                posicion[1] += inclinacion.sin();
+                posicion[2] += rumbo.sin() * inclinacion.cos();
+                posicion[1] = posicion[1].max(1.5);
+
+                // El giro cambia despacio para que el túnel serpentee sin zigzaguear
+                giro = giro * 0.75 + rng.gen_range(-0.15..0.15);
+                rumbo += giro;
+                inclinacion = inclinacion * 0.7 + rng.gen_range(-0.2..0.2);
+            }
+        }
+        excavados
+    }
+
+    fn excavar_esfera(&self, mundo: &mut Mundo, centro: [f64; 3], radio: f64,
+                      minimo: (i32, i32), maximo: (i32, i32)) -> usize {
+        let alcance = radio.ceil() as i32;
+        let (cx, cy, cz) = (centro[0].floor() as i32, centro[1].floor() as i32, centro[2].floor() as i32);
+        let mut excavados = 0;
+        for x in (cx - alcance).max(minimo.0)..=(cx + alcance).min(maximo.0) {
+            for z in (cz - alcance).max(minimo.1)..=(cz + alcance).min(maximo.1) {
+                for y in (cy - alcance).max(1)..=cy + alcance {
+                    let d = [x as f64 + 0.5 - centro[0], y as f64 + 0.5 - centro[1], z as f64 + 0.5 - centro[2]];
+                    if d[0] * d[0] + d[1] * d[1] + d[2] * d[2] > radio * radio {
+                        continue;
+                    }
+                    // La capa de superficie también se abre para formar entradas
+                    let id = mundo.obtener(x, y, z);
+                    if (excavable(id) || matches!(id, PASTO | NIEVE)) && !junto_a_liquido(mundo, x, y, z) {
+                        mundo.colocar(x, y, z, AIRE);
+                        excavados += 1;
+                    }
+                }
+            }
+        }
+        excavados
+    }
+
+    /// Grupos de mineral de cada veta: se elige una columna y una profundidad según la
+    /// distribución de la veta y un recorrido aleatorio convierte la piedra que encuentra.
+    /// Devuelve (grupos, bloques) colocados.
+    pub fn colocar_vetas(&self, mundo: &mut Mundo, minimo: (i32, i32), maximo: (i32, i32)) -> (usize, usize) {
+        let (mut grupos, mut bloques) = (0, 0);
+        for (n, veta) in self.config.vetas.iter().enumerate() {
+            let mut rng = self.aleatorio(2 + n as u64);
+            for _ in 0..Self::cantidad(veta.grupos_por_chunk, minimo, maximo) {
+                let x = rng.gen_range(minimo.0..=maximo.0);
+                let z = rng.gen_range(minimo.1..=maximo.1);
+                let profundidad = triangular(
+                    &mut rng,
+                    veta.profundidad_min as f64,
+                    veta.profundidad_pico as f64,
+                    veta.profundidad_max as f64,
+                );
+                let superficie = match mundo.superficie(x, z) {
+                    Some(y) => y,
+                    None => continue,
+                };
+                let mut posicion = (x, superficie - profundidad.round() as i32, z);
+
+                let mut colocados = 0;
+                for _ in 0..veta.tamano {
+                    if mundo.obtener(posicion.0, posicion.1, posicion.2) == PIEDRA
+                        && posicion.0 >= minimo.0 && posicion.0 <= maximo.0
+                        && posicion.2 >= minimo.1 && posicion.2 <= maximo.1
+                    {
+                        mundo.colocar(posicion.0, posicion.1, posicion.2, veta.id);
+                        colocados += 1;
+                    }
+                    match rng.gen_range(0..6) {
+                        0 => posicion.0 += 1,
+                        1 => posicion.0 -= 1,
+                        2 => posicion.1 += 1,
+                        3 => posicion.1 -= 1,
+                        4 => posicion.2 += 1,
+                        _ => posicion.2 -= 1,
+                    }
+                }
+                if colocados > 0 {
+                    grupos += 1;
+                    bloques += colocados;
+                }
+            }
+        }
+        (grupos, bloques)
+    }
+}
@@ -0,0 +1,227 @@
+use crate::erosion::{erosionar, ConfiguracionErosion, MapaAlturas, ResumenErosion};
+use crate::mundo::*;
+use crate::ruido::Perlin;
+
+// Columnas de más que se erosionan alrededor de la región pedida
+const MARGEN_EROSION: i32 = 8;
+
+/// Bioma de una columna, elegido por altura, temperatura, humedad y erosión
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bioma {
+    Llanura,
+    Bosque,
+    Desierto,
+    MontanaNevada,
+    Cerezal,
+}
+
+impl Bioma {
+    /// Bloque de la superficie
+    pub fn bloque_superficie(&self) -> IdBloque {
+        match self {
+            Bioma::Llanura | Bioma::Bosque | Bioma::Cerezal => PASTO,
+            Bioma::Desierto => ARENA,
+            Bioma::MontanaNevada => NIEVE,
+        }
+    }
+
+    /// Bloque de las capas bajo la superficie (antes de la piedra)
+    pub fn bloque_subsuelo(&self) -> IdBloque {
+        match self {
+            Bioma::Llanura | Bioma::Bosque | Bioma::Cerezal => TIERRA,
+            Bioma::Desierto => ARENA,
+            Bioma::MontanaNevada => PIEDRA,
+        }
+    }
+
+    /// Probabilidad de que una columna tenga un árbol
+    pub fn densidad_vegetacion(&self) -> f64 {
+        match self {
+            Bioma::Llanura => 0.01,
+            Bioma::Bosque => 0.08,
+            Bioma::Desierto => 0.002,
+            Bioma::MontanaNevada => 0.005,
+            Bioma::Cerezal => 0.04,
+        }
+    }
+}
+
+/// Forma del relieve; las distancias están en bloques
+#[derive(Clone, Debug)]
+pub struct ConfiguracionTerreno {
+    pub nivel_mar: i32,            // Las columnas más bajas se llenan de agua hasta aquí
+    pub altura_base: f64,          // Altura media sobre el nivel del mar
+    pub relieve: f64,              // Amplitud de las colinas
+    pub altura_montanas: f64,      // Altura extra de las cordilleras donde la erosión es baja
+    pub escala: f64,               // Tamaño de las colinas
+    pub escala_biomas: f64,        // Tamaño de las regiones de clima
+    pub linea_nieve: i32,          // Por encima todo es montaña nevada
+    pub capas_subsuelo: i32,       // Bloques de subsuelo entre la superficie y la piedra
+    pub octavas: u32,
+}
+
+impl Default for ConfiguracionTerreno {
+    fn default() -> Self {
+        Self {
+            nivel_mar: 11,
+            altura_base: 3.0,
+            relieve: 3.0,
+            altura_montanas: 9.0,
+            escala: 24.0,
+            escala_biomas: 40.0,
+            linea_nieve: 20,
+            capas_subsuelo: 3,
+            octavas: 4,
+        }
+    }
+}
+
+/// Altura y bioma de una columna
+#[derive(Clone, Copy, Debug)]
+pub struct MuestraTerreno {
+    pub altura: i32,               // y del bloque de superficie
+    pub bioma: Bioma,
+}
+
+/// Generador de terreno reproducible: la misma semilla da siempre el mismo mundo
+pub struct GeneradorTerreno {
+    pub semilla: u64,
+    pub config: ConfiguracionTerreno,
+    continentalidad: Perlin,
+    erosion: Perlin,
+    relieve: Perlin,
+    temperatura: Perlin,
+    humedad: Perlin,
+}
+
+impl GeneradorTerreno {
+    pub fn nuevo(semilla: u64) -> Self {
+        Self::con_configuracion(semilla, ConfiguracionTerreno::default())
+    }
+
+    pub fn con_configuracion(semilla: u64, config: ConfiguracionTerreno) -> Self {
+        // Un ruido independiente por mapa
+        let ruido = |mapa: u64| Perlin::nuevo(semilla.wrapping_mul(31).wrapping_add(mapa));
+        Self {
+            semilla,
+            config,
+            continentalidad: ruido(1),
+            erosion: ruido(2),
+            relieve: ruido(3),
+            temperatura: ruido(4),
+            humedad: ruido(5),
+        }
+    }
+
+    // Mapa de clima con la escala de los biomas
+    fn clima(&self, ruido: &Perlin, x: f64, z: f64) -> f64 {
+        let escala = self.config.escala_biomas;
+        ruido.fractal2(x / escala, z / escala, 3) * 1.5
+    }
+
+    // Las cordilleras solo aparecen donde la erosión es baja
+    fn montana(erosion: f64) -> f64 {
+        ((-erosion - 0.1) / 0.5).clamp(0.0, 1.0)
+    }
+
+    /// Altura del relieve sin redondear a bloques
+    pub fn altura_continua(&self, x: i32, z: i32) -> f64 {
+        let c = &self.config;
+        let (x, z) = (x as f64, z as f64);
+        let continentalidad = self.clima(&self.continentalidad, x, z).clamp(-1.0, 1.0);
+        let montana = Self::montana(self.clima(&self.erosion, x, z).clamp(-1.0, 1.0));
+        let colinas = self.relieve.fractal2(x / c.escala, z / c.escala, c.octavas);
+        let crestas = self.relieve.crestas2(x / c.escala + 91.7, z / c.escala - 37.3, c.octavas);
+
+        c.nivel_mar as f64
+            + c.altura_base * (0.5 + continentalidad)
+            + colinas * c.relieve * (1.0 - 0.5 * montana)
+            + crestas * montana * c.altura_montanas
+    }
+
+    /// Muestra de la columna con otra altura (por ejemplo tras la erosión). El bioma se
+    /// decide con la altura ya redondeada a bloques.
+    pub fn muestra_con_altura(&self, x: i32, z: i32, altura: f64) -> MuestraTerreno {
+        let c = &self.config;
+        let altura = altura.round().max(1.0) as i32;
+        let (x, z) = (x as f64, z as f64);
+        let clima = |ruido: &Perlin| self.clima(ruido, x, z);
+        let erosion = clima(&self.erosion).clamp(-1.0, 1.0);
+        let montana = Self::montana(erosion);
+
+        // Hace más frío con la altura
+        let temperatura = clima(&self.temperatura) - (altura - c.nivel_mar) as f64 * 0.03;
+        let humedad = clima(&self.humedad);
+
+        let bioma = if altura >= c.linea_nieve || (montana > 0.5 && temperatura < -0.2) {
+            Bioma::MontanaNevada
+        } else if temperatura > 0.3 && humedad < 0.0 {
+            Bioma::Desierto
+        } else if montana > 0.2 && humedad > 0.0 {
+            Bioma::Cerezal
+        } else if humedad > 0.15 {
+            Bioma::Bosque
+        } else {
+            Bioma::Llanura
+        };
+
+        MuestraTerreno { altura, bioma }
+    }
+
+    /// Llena la columna con la muestra: piedra, subsuelo y superficie del bioma, y agua
+    /// hasta el nivel del mar. Las orillas son de arena y en las laderas altas asoma la piedra.
+    pub fn rellenar_columna(&self, mundo: &mut Mundo, x: i32, z: i32, mut muestra: MuestraTerreno) -> MuestraTerreno {
+        let c = &self.config;
+        muestra.altura = muestra.altura.min(mundo.altura as i32 - 1);
+        let altura = muestra.altura;
+
+        let orilla = altura <= c.nivel_mar && muestra.bioma != Bioma::MontanaNevada;
+        let superficie = if orilla {
+            ARENA
+        } else if muestra.bioma == Bioma::MontanaNevada && altura < c.linea_nieve - 2 {
+            PIEDRA
+        } else {
+            muestra.bioma.bloque_superficie()
+        };
+        let subsuelo = if orilla { ARENA } else { muestra.bioma.bloque_subsuelo() };
+
+        mundo.rellenar((x, 0, z), (x, altura - c.capas_subsuelo - 1, z), PIEDRA);
+        mundo.rellenar((x, (altura - c.capas_subsuelo).max(0), z), (x, altura - 1, z), subsuelo);
+        mundo.colocar(x, altura, z, superficie);
+        if altura < c.nivel_mar {
+            mundo.rellenar((x, altura + 1, z), (x, c.nivel_mar, z), AGUA);
+        }
+        muestra
+    }
+
+    /// Mapa de alturas continuas de la región
+    pub fn mapa_alturas(&self, minimo: (i32, i32), maximo: (i32, i32)) -> MapaAlturas {
+        MapaAlturas::nuevo(minimo, maximo, |x, z| self.altura_continua(x, z))
+    }
+
+    /// Genera las columnas del mapa de `minimo` a `maximo` con sus alturas redondeadas
+    /// a bloques
+    pub fn generar_desde_mapa(&self, mundo: &mut Mundo, mapa: &MapaAlturas, minimo: (i32, i32), maximo: (i32, i32)) {
+        for x in minimo.0..=maximo.0 {
+            for z in minimo.1..=maximo.1 {
+                if let Some(altura) = mapa.altura(x, z) {
+                    self.rellenar_columna(mundo, x, z, self.muestra_con_altura(x, z, altura));
+                }
+            }
+        }
+    }
+
+    /// Genera la región con el relieve erosionado (ver `erosion::erosionar`). Se erosiona
+    /// un margen alrededor para que el material que las gotas sacan por el borde del
+    /// mapa no rebaje la región. El resultado depende solo de la semilla y la configuración.
+    pub fn generar_erosionado(&self, mundo: &mut Mundo, minimo: (i32, i32), maximo: (i32, i32),
+                              config: &ConfiguracionErosion) -> ResumenErosion {
+        let mut mapa = self.mapa_alturas(
+            (minimo.0 - MARGEN_EROSION, minimo.1 - MARGEN_EROSION),
+            (maximo.0 + MARGEN_EROSION, maximo.1 + MARGEN_EROSION),
+        );
+        let resumen = erosionar(&mut mapa, config, self.semilla);
+        self.generar_desde_mapa(mundo, &mapa, minimo, maximo);
+        resumen
+    }
+}
@@ -0,0 +1,170 @@
+use nalgebra::{Matrix3, Vector3};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Curva que lleva la radiancia (sin límite) al rango [0, 1] de la pantalla
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OperadorTonos {
+    /// Recorta en 1.0
+    Lineal,
+    /// Reinhard extendido sobre la luminancia; `blanco` es la radiancia que llega a 1.0
+    Reinhard { blanco: f64 },
+    /// Ajuste de la curva filmica ACES (RRT + ODT)
+    Aces,
+}
+
+/// Exposición y curva de tonos con las que se revela la imagen HDR
+#[derive(Clone, Copy, Debug)]
+pub struct Revelado {
+    pub exposicion: f64,     // En pasos (EV): +1 duplica el brillo
+    pub operador: OperadorTonos,
+}
+
+impl Default for Revelado {
+    fn default() -> Self {
+        Self {
+            exposicion: 0.0,
+            operador: OperadorTonos::Aces,
+        }
+    }
+}
+
+impl Revelado {
+    pub fn nuevo(exposicion: f64, operador: OperadorTonos) -> Self {
+        Self { exposicion, operador }
+    }
+
+    /// Sin exposición ni curva: para imágenes que ya están en [0, 1] (mapas de calor, AOVs)
+    pub fn lineal() -> Self {
+        Self::nuevo(0.0, OperadorTonos::Lineal)
+    }
+
+    /// Radiancia escalada por la exposición, todavía en HDR
+    pub fn exponer(&self, color: &Vector3<f64>) -> Vector3<f64> {
+        color * 2f64.powf(self.exposicion)
+    }
+
+    /// Color lineal de pantalla en [0, 1]
+    pub fn mapear(&self, color: &Vector3<f64>) -> Vector3<f64> {
+        let color = self.exponer(color).map(|c| c.max(0.0));
+        let mapeado = match self.operador {
+            OperadorTonos::Lineal => color,
+            OperadorTonos::Reinhard { blanco } => {
+                let luminancia = 0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z;
+                if luminancia <= 0.0 {
+                    return Vector3::zeros();
+                }
+                let nueva = luminancia * (1.0 + luminancia / (blanco * blanco)) / (1.0 + luminancia);
+                color * (nueva / luminancia)
+            }
+            OperadorTonos::Aces => aces(&color),
+        };
+        mapeado.map(|c| c.clamp(0.0, 1.0))
+    }
+
+    /// Revela el búfer a bytes RGBA codificados en sRGB
+    pub fn a_rgba8(&self, pixeles: &[Vector3<f64>]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(pixeles.len() * 4);
+        for pixel in pixeles {
+            let color = self.mapear(pixel);
+            for canal in 0..3 {
+                bytes.push((codificar_srgb(color[canal]) * 255.0 + 0.5) as u8);
+            }
+            bytes.push(255);
+        }
+        bytes
+    }
+}
+
+// Ajuste de Stephen Hill: entrada sRGB lineal -> ACES, curva RRT+ODT y vuelta
+fn aces(color: &Vector3<f64>) -> Vector3<f64> {
+    let entrada = Matrix3::new(
+        0.59719, 0.35458, 0.04823,
+        0.07600, 0.90834, 0.01566,
+        0.02840, 0.13383, 0.83777,
+    );
+    let salida = Matrix3::new(
+        1.60475, -0.53108, -0.07367,
+        -0.10208, 1.10813, -0.00605,
+        -0.00327, -0.07276, 1.07602,
+    );
+
+    let v = entrada * color;
+    let curva = v.map(|x| (x * (x + 0.0245786) - 0.000090537) / (x * (0.983729 * x + 0.4329510) + 0.238081));
+    salida * curva
+}
+
+/// Función de transferencia sRGB (lineal -> codificado)
+pub fn codificar_srgb(lineal: f64) -> f64 {
+    let c = lineal.clamp(0.0, 1.0);
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Guarda el búfer según la extensión: `.exr` y `.hdr` conservan la radiancia en
+/// coma flotante (solo se aplica la exposición); el resto se revela a 8 bits sRGB.
+pub fn guardar_imagen(ruta: &Path, ancho: usize, alto: usize, pixeles: &[Vector3<f64>],
+                      revelado: &Revelado) -> Result<(), String> {
+    if pixeles.len() != ancho * alto {
+        return Err("Tamaño de búfer incorrecto".to_string());
+    }
+    let error = |e: image::ImageError| format!("No se pudo guardar {}: {}", ruta.display(), e);
+    let extension = ruta
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "exr" => {
+            let flotantes = pixeles
+                .iter()
+                .flat_map(|p| {
+                    let p = revelado.exponer(p);
+                    [p.x as f32, p.y as f32, p.z as f32]
+                })
+                .collect();
+            let imagen = image::Rgb32FImage::from_raw(ancho as u32, alto as u32, flotantes)
+                .ok_or_else(|| "Tamaño de búfer incorrecto".to_string())?;
+            imagen.save(ruta).map_err(error)
+        }
+        "hdr" => {
+            let flotantes: Vec<image::Rgb<f32>> = pixeles
+                .iter()
+                .map(|p| {
+                    let p = revelado.exponer(p).map(|c| c.max(0.0));
+                    image::Rgb([p.x as f32, p.y as f32, p.z as f32])
+                })
+                .collect();
+            let archivo = File::create(ruta).map_err(|e| format!("No se pudo crear {}: {}", ruta.display(), e))?;
+            image::codecs::hdr::HdrEncoder::new(BufWriter::new(archivo))
+                .encode(&flotantes, ancho, alto)
+                .map_err(error)
+        }
+        _ => {
+            let imagen = image::RgbaImage::from_raw(ancho as u32, alto as u32, revelado.a_rgba8(pixeles))
+                .ok_or_else(|| "Tamaño de búfer incorrecto".to_string())?;
+            imagen.save(ruta).map_err(error)
+        }
+    }
+}
+
+/// Guarda valores en [0, 1] como PNG sin curva ni codificación sRGB (normales, máscaras)
+pub fn guardar_png_datos(ruta: &Path, ancho: usize, alto: usize, valores: &[Vector3<f64>]) -> Result<(), String> {
+    let bytes = valores
+        .iter()
+        .flat_map(|v| {
+            let v = v.map(|c| (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8);
+            [v.x, v.y, v.z]
+        })
+        .collect();
+    let imagen = image::RgbImage::from_raw(ancho as u32, alto as u32, bytes)
+        .ok_or_else(|| "Tamaño de búfer incorrecto".to_string())?;
+    imagen
+        .save(ruta)
+        .map_err(|e| format!("No se pudo guardar {}: {}", ruta.display(), e))
+}